@@ -1,82 +1,241 @@
-use regex::Regex;
-use serde::{Deserialize, Serialize};
-use std::borrow::Borrow;
-use std::error::Error;
-use std::fmt::format;
-use std::future::Future;
-use std::ops::Index;
+use serde::Serialize;
 
-extern crate reqwest;
 
 use crate::models::clan::{Clan, ClanMember};
 use crate::models::current_war::War;
 use crate::models::gold_pass::GoldPass;
+use crate::models::paging::Page;
+use crate::models::ranking::{ClanRanking, PlayerRanking};
+use crate::models::tag::Tag;
 use crate::models::player::{Player, PlayerToken};
 
-use reqwest::header::{HeaderMap, HeaderValue};
-use reqwest::RequestBuilder;
+use http::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use http::{Method, StatusCode};
 use serde::de::DeserializeOwned;
 use crate::models::war_log::WarLog;
 
-use std::sync::Mutex;
-use lazy_static::lazy_static;
+use std::sync::{Arc, Mutex};
+use crate::error;
+pub use crate::error::{ApiError, DeserializeError};
+use crate::cache::{self, CacheBackend, CacheEntry, MemoryCache};
+#[cfg(feature = "dev")]
 use crate::dev;
-
-use futures;
-
-
-#[macro_use]
-#[derive(Debug)]
+#[cfg(feature = "dev")]
+use crate::dev_models::existing_key::Key;
+#[cfg(feature = "reqwest")]
+use crate::http::ReqwestTransport;
+use crate::http::{HttpTransport, Request, Response, TransportError, TransportErrorKind};
+use crate::maintenance::{self, CircuitBreaker};
+use crate::middleware::Middleware;
+use crate::query::{ClanSearch, Query};
+use tokio::sync::watch;
+use crate::rate_limit::{Priority, RateLimit, RateLimiter};
+use crate::retry::{self, RetryPolicy};
+
+use futures::future::{BoxFuture, FutureExt, Shared};
+use futures::stream::{self, Stream, StreamExt};
+use std::collections::HashMap;
+use tracing::{debug, field, info_span, instrument, trace, Instrument, Span};
+use std::time::{Duration, Instant};
+
+
+#[derive(Debug, Clone)]
 pub struct Client {
-    username: String,
-    password: String
+    rate_limiter: Option<Arc<RateLimiter>>,
+    retry_policy: Option<RetryPolicy>,
+    cache: Option<Arc<dyn CacheBackend>>,
+    use_cache: bool,
+    bulk_concurrency: usize,
+    priority: Priority,
+    maintenance: Arc<CircuitBreaker>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    in_flight: Arc<Mutex<HashMap<String, InFlight>>>,
+    keys: Arc<Mutex<Vec<ApiKey>>>,
+    egress: Arc<Vec<Arc<dyn HttpTransport>>>,
 }
 
-#[derive(Debug)]
-pub enum ApiError {
-    Request(reqwest::Error),
-    Api(reqwest::StatusCode),
-}
-lazy_static! {
-    static ref TOKEN_LIST: Mutex<Vec<String>> = Mutex::new(vec![]);
-    static ref s_Client: reqwest::Client = reqwest::Client::new();
-}
+type InFlight = Shared<BoxFuture<'static, Result<Response, ApiError>>>;
 
 const BASE_URL: &str = "https://api.clashofclans.com/v1";
 
 impl Client {
-    pub async fn new(username: String, password: String) -> Self {
-        let client = Self {
-            username,
-            password,
-        };
-        let _ = futures::future::join_all(client.init());
+    #[cfg(feature = "dev")]
+    pub async fn new(username: String, password: String) -> Result<Self, ApiError> {
+        let client = Self::with_egress(vec![Arc::new(ReqwestTransport::new())]);
+        client.init(username, password).await?;
+        Ok(client)
+    }
+
+    /// Creates a client from already known API keys, skipping the developer portal login.
+    #[cfg(feature = "reqwest")]
+    pub fn from_keys(keys: Vec<String>) -> Self {
+        Self::with_transport(ReqwestTransport::new(), keys)
+    }
+
+    /// Creates a client from already known API keys that sends every request through `transport`.
+    pub fn with_transport(transport: impl HttpTransport + 'static, keys: Vec<String>) -> Self {
+        let client = Self::with_egress(vec![Arc::new(transport)]);
+        *client.keys.lock().unwrap() = keys
+            .into_iter()
+            .enumerate()
+            .map(|(i, key)| ApiKey {
+                id: i.to_string(),
+                key,
+                egress: 0,
+            })
+            .collect();
         client
     }
 
-    async fn init(&self) {
-        let mut result = dev::get_keys(self.username.to_string(), self.password.to_string()).await;
-        result.remove_all_invalid_keys(dev::get_ip().await.unwrap());
+    /// Creates a client that sends each key's requests through the proxy whose
+    /// public IP the key is registered for. Proxies may be `http://` or `https://` urls,
+    /// or `socks5://` with the `socks` feature.
+    #[cfg(feature = "dev")]
+    pub async fn with_proxies(
+        username: String,
+        password: String,
+        proxies: Vec<String>,
+    ) -> Result<Self, ApiError> {
+        let egress = proxies
+            .iter()
+            .map(|proxy| {
+                reqwest::Proxy::all(proxy.as_str())
+                    .and_then(|proxy| reqwest::Client::builder().proxy(proxy).build())
+                    .map(|client| Arc::new(ReqwestTransport::from_client(client)) as Arc<dyn HttpTransport>)
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ApiError::from(TransportError::from(e)))?;
+        let client = Self::with_egress(egress);
+        client.init(username, password).await?;
+        Ok(client)
+    }
 
-        //add keys to global list
-        for key in result.keys() {
-            TOKEN_LIST.lock().unwrap().push(key.key().to_string())
+    fn with_egress(egress: Vec<Arc<dyn HttpTransport>>) -> Self {
+        Self {
+            rate_limiter: None,
+            retry_policy: None,
+            cache: None,
+            use_cache: true,
+            bulk_concurrency: 10,
+            priority: Priority::Normal,
+            maintenance: Arc::new(CircuitBreaker::new(Duration::from_secs(30))),
+            middleware: Vec::new(),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            keys: Arc::new(Mutex::new(Vec::new())),
+            egress: Arc::new(egress),
         }
     }
 
-    fn get(&self, url: String) -> Result<reqwest::RequestBuilder, reqwest::Error> {
-        let res = s_Client
-            .get(url)
-            .bearer_auth(&self.cycle());
-        Ok(res)
+    /// Limits how fast requests are sent with each key. Requests are queued
+    /// until a key has capacity instead of running into 429s.
+    pub fn with_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limiter = Some(Arc::new(RateLimiter::new(limit)));
+        self
     }
 
-    fn post(&self, url: String, body: String) -> Result<reqwest::RequestBuilder, reqwest::Error> {
-        let res = s_Client
-            .post(url)
-            .bearer_auth(&self.cycle())
-            .body(body);
-        Ok(res)
+    /// Retries transient failures according to `policy`.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Caches successful GET responses for as long as their `Cache-Control: max-age`
+    /// allows, in a default sized `MemoryCache`. The cache is shared by every clone of this client.
+    pub fn with_cache(self) -> Self {
+        self.with_cache_backend(MemoryCache::default())
+    }
+
+    pub fn with_cache_backend(mut self, backend: impl CacheBackend + 'static) -> Self {
+        self.cache = Some(Arc::new(backend));
+        self
+    }
+
+    /// Returns a handle to this client whose requests skip the cache.
+    pub fn without_cache(&self) -> Self {
+        Self {
+            use_cache: false,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a handle to this client whose requests are scheduled with `priority`
+    /// by the rate limiter. Without a rate limit, priorities have no effect.
+    pub fn at_priority(&self, priority: Priority) -> Self {
+        Self {
+            priority,
+            ..self.clone()
+        }
+    }
+
+    /// How often a single request is let through to check whether maintenance is over.
+    /// While no caller sends one, the client probes `/goldpass/seasons/current` itself.
+    /// Defaults to 30 seconds.
+    pub fn with_maintenance_probe_interval(mut self, interval: Duration) -> Self {
+        self.maintenance = Arc::new(CircuitBreaker::new(interval));
+        self
+    }
+
+    pub fn in_maintenance(&self) -> bool {
+        self.maintenance.in_maintenance()
+    }
+
+    /// Receives `true` when maintenance starts and `false` once it is over.
+    pub fn subscribe_maintenance(&self) -> watch::Receiver<bool> {
+        self.maintenance.subscribe()
+    }
+
+    /// Maximum number of requests `get_players`/`get_clans` keep in flight at once.
+    pub fn with_bulk_concurrency(mut self, limit: usize) -> Self {
+        self.bulk_concurrency = limit.max(1);
+        self
+    }
+
+    /// Adds `middleware` to the end of the hook chain.
+    pub fn with_middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+    }
+
+    #[cfg(feature = "dev")]
+    #[instrument(skip_all)]
+    /// Fails with `ApiError::NoKeys` if none of the account's keys is registered
+    /// for the public IP of any egress.
+    async fn init(&self, username: String, password: String) -> Result<(), ApiError> {
+        let result = dev::get_keys(username, password).await?;
+
+        let mut ips = Vec::with_capacity(self.egress.len());
+        for (egress, transport) in self.egress.iter().enumerate() {
+            match dev::get_ip(transport.as_ref()).await {
+                Ok(ip) => ips.push(Some(ip)),
+                Err(e) => {
+                    tracing::warn!(egress, error = %e, "could not resolve public ip, skipping egress");
+                    ips.push(None);
+                }
+            }
+        }
+
+        let keys = match_keys(result.keys(), &ips);
+        if keys.is_empty() {
+            return Err(ApiError::NoKeys);
+        }
+        *self.keys.lock().unwrap() = keys;
+        Ok(())
+    }
+
+    fn get(&self, url: String) -> Request {
+        Request::new(Method::GET, url)
+    }
+
+    fn post(&self, url: String, body: String) -> Request {
+        let mut req = Request::new(Method::POST, url).with_body(body);
+        req.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        req
     }
 
     ///                                                            ///
@@ -84,169 +243,608 @@ impl Client {
     /// --------------------------END POINTS-----------------------///
     ///                                                            ///
     ///                                                            ///
-    pub async fn get_clan(&self, tag: String) -> Result<Clan, ApiError> {
-        let url = format!("{}/clans/{}", BASE_URL, self.format_tag(tag));
+    #[instrument(skip(self, tag), fields(tag))]
+    pub async fn get_clan<T>(&self, tag: T) -> Result<Clan, ApiError>
+    where
+        T: TryInto<Tag>,
+        ApiError: From<T::Error>,
+    {
+        let url = format!("{}/clans/{}", BASE_URL, parse_tag(tag)?.url_encoded());
         self.parse_json::<Clan>(self.get(url)).await
     }
 
-    pub async fn get_player(&self, tag: String) -> Result<Player, ApiError> {
-        let url = format!("{}/players/{}", BASE_URL, self.format_tag(tag));
+    #[instrument(skip(self, tag), fields(tag))]
+    pub async fn get_player<T>(&self, tag: T) -> Result<Player, ApiError>
+    where
+        T: TryInto<Tag>,
+        ApiError: From<T::Error>,
+    {
+        let url = format!("{}/players/{}", BASE_URL, parse_tag(tag)?.url_encoded());
         self.parse_json::<Player>(self.get(url)).await
     }
 
-    pub async fn get_current_war(&self, tag: String) -> Result<War, ApiError> {
-        let url = format!("{}/clans/{}/currentwar", BASE_URL, self.format_tag(tag));
+    /// Fetches every player in `tags`, yielding `(tag, result)` pairs as they complete.
+    /// A failed tag does not stop the rest of the batch. Results are keyed by the
+    /// normalized tag, e.g. `#2PP` for `2pp`, or by the input itself if it is not a valid tag.
+    pub fn get_players<'a, I, T>(&'a self, tags: I) -> impl Stream<Item = (String, Result<Player, ApiError>)> + 'a
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: 'a,
+        T: TryInto<Tag> + ToString + 'a,
+        ApiError: From<T::Error>,
+    {
+        stream::iter(tags)
+            .map(move |tag| async move {
+                let (key, tag) = parse_bulk_tag(tag);
+                match tag {
+                    Ok(tag) => (key, self.get_player::<&Tag>(&tag).await),
+                    Err(e) => (key, Err(e)),
+                }
+            })
+            .buffer_unordered(self.bulk_concurrency)
+    }
+
+    /// Fetches every clan in `tags`, yielding `(tag, result)` pairs as they complete.
+    /// A failed tag does not stop the rest of the batch. Results are keyed like `get_players`.
+    pub fn get_clans<'a, I, T>(&'a self, tags: I) -> impl Stream<Item = (String, Result<Clan, ApiError>)> + 'a
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: 'a,
+        T: TryInto<Tag> + ToString + 'a,
+        ApiError: From<T::Error>,
+    {
+        stream::iter(tags)
+            .map(move |tag| async move {
+                let (key, tag) = parse_bulk_tag(tag);
+                match tag {
+                    Ok(tag) => (key, self.get_clan::<&Tag>(&tag).await),
+                    Err(e) => (key, Err(e)),
+                }
+            })
+            .buffer_unordered(self.bulk_concurrency)
+    }
+
+    #[instrument(skip(self, tag), fields(tag))]
+    pub async fn get_current_war<T>(&self, tag: T) -> Result<War, ApiError>
+    where
+        T: TryInto<Tag>,
+        ApiError: From<T::Error>,
+    {
+        let url = format!("{}/clans/{}/currentwar", BASE_URL, parse_tag(tag)?.url_encoded());
         self.parse_json::<War>(self.get(url)).await
     }
 
-    pub async fn get_goldpass(&self, tag: String) -> Result<GoldPass, ApiError> {
+    #[instrument(skip(self))]
+    pub async fn get_goldpass(&self) -> Result<GoldPass, ApiError> {
         let url = format!("{}/goldpass/seasons/current", BASE_URL);
         self.parse_json::<GoldPass>(self.get(url)).await
     }
 
-    pub async fn get_verified_player(
+    #[instrument(skip(self, tag, token), fields(tag))]
+    pub async fn get_verified_player<T>(&self, tag: T, token: String) -> Result<PlayerToken, ApiError>
+    where
+        T: TryInto<Tag>,
+        ApiError: From<T::Error>,
+    {
+        let url = format!("{}/players/{}/verifytoken", BASE_URL, parse_tag(tag)?.url_encoded());
+        let body = serde_json::to_string(&VerifyToken { token }).unwrap();
+        self.parse_json::<PlayerToken>(self.post(url, body)).await
+    }
+
+    #[instrument(skip(self, tag), fields(tag))]
+    pub async fn get_clan_members<T>(&self, tag: T, query: Query) -> Result<Page<ClanMember>, ApiError>
+    where
+        T: TryInto<Tag>,
+        ApiError: From<T::Error>,
+    {
+        let url = format!("{}/clans/{}/members", BASE_URL, parse_tag(tag)?.url_encoded());
+        self.get_page(url, query).await
+    }
+
+    #[instrument(skip(self, tag), fields(tag))]
+    pub async fn get_clan_warlog<T>(&self, tag: T, query: Query) -> Result<Page<WarLog>, ApiError>
+    where
+        T: TryInto<Tag>,
+        ApiError: From<T::Error>,
+    {
+        let url = format!("{}/clans/{}/warlog", BASE_URL, parse_tag(tag)?.url_encoded());
+        self.get_page(url, query).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn search_clans(&self, search: ClanSearch) -> Result<Page<Clan>, ApiError> {
+        let url = format!("{}/clans", BASE_URL);
+        self.get_page(url, search.into()).await
+    }
+
+    /// `location_id` is a location such as `32000006` (International) for global rankings.
+    #[instrument(skip(self))]
+    pub async fn get_clan_rankings(&self, location_id: u32, query: Query) -> Result<Page<ClanRanking>, ApiError> {
+        let url = format!("{}/locations/{}/rankings/clans", BASE_URL, location_id);
+        self.get_page(url, query).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_player_rankings(&self, location_id: u32, query: Query) -> Result<Page<PlayerRanking>, ApiError> {
+        let url = format!("{}/locations/{}/rankings/players", BASE_URL, location_id);
+        self.get_page(url, query).await
+    }
+
+    /// The page after `page`, or `None` if it was the last one.
+    #[instrument(skip_all, fields(url = page.query()))]
+    pub async fn next_page<T: DeserializeOwned>(&self, page: &Page<T>) -> Result<Option<Page<T>>, ApiError> {
+        match (page.query(), page.next_cursor()) {
+            (Some(query), Some(after)) => self.replay(query, Query::new().after(after)).await.map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// The page before `page`, or `None` if it was the first one.
+    #[instrument(skip_all, fields(url = page.query()))]
+    pub async fn prev_page<T: DeserializeOwned>(&self, page: &Page<T>) -> Result<Option<Page<T>>, ApiError> {
+        match (page.query(), page.prev_cursor()) {
+            (Some(query), Some(before)) => self.replay(query, Query::new().before(before)).await.map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Every member of the clan, fetched page by page. Stops after `max_items` if given.
+    pub fn get_clan_members_stream<'a, T>(
+        &'a self,
+        tag: T,
+        max_items: Option<usize>,
+    ) -> impl Stream<Item = Result<ClanMember, ApiError>> + 'a
+    where
+        T: TryInto<Tag> + 'a,
+        ApiError: From<T::Error>,
+    {
+        let span = info_span!("get_clan_members_stream", tag = field::Empty);
+        let url = span
+            .in_scope(|| parse_tag(tag))
+            .map(|tag| format!("{}/clans/{}/members", BASE_URL, tag.url_encoded()));
+        self.paginate(span, url, Query::new(), max_items)
+    }
+
+    /// The clan's whole war log, fetched page by page. Stops after `max_items` if given.
+    pub fn get_clan_warlog_stream<'a, T>(
+        &'a self,
+        tag: T,
+        max_items: Option<usize>,
+    ) -> impl Stream<Item = Result<WarLog, ApiError>> + 'a
+    where
+        T: TryInto<Tag> + 'a,
+        ApiError: From<T::Error>,
+    {
+        let span = info_span!("get_clan_warlog_stream", tag = field::Empty);
+        let url = span
+            .in_scope(|| parse_tag(tag))
+            .map(|tag| format!("{}/clans/{}/warlog", BASE_URL, tag.url_encoded()));
+        self.paginate(span, url, Query::new(), max_items)
+    }
+
+    /// Every clan matching `search`, fetched page by page. Stops after `max_items` if given.
+    pub fn search_clans_stream(
         &self,
-        tag: String,
-        token: String,
-    ) -> Result<PlayerToken, ApiError> {
-        let url = format!("{}/players/{}/verifytoken", BASE_URL, self.format_tag(tag));
-        let token = format!("{{\"token\":\"{}\"}}", token);
-        self.parse_json::<PlayerToken>(self.post(url, token)).await
+        search: ClanSearch,
+        max_items: Option<usize>,
+    ) -> impl Stream<Item = Result<Clan, ApiError>> + '_ {
+        let span = info_span!("search_clans_stream", ?search);
+        let url = format!("{}/clans", BASE_URL);
+        self.paginate(span, Ok(url), search.into(), max_items)
     }
 
-    pub async fn get_clan_members(&self, tag: String, config: ConfigForRezponse) -> Result<Rezponse<ClanMember>, ApiError> {
-        let mut url = format!("https://api.clashofclans.com/v1/clans/{}/members", self.format_tag(tag));
-        url = self.get_cursor_url(url, config);
-        self.parse_json::<Rezponse<ClanMember>>(self.get(url)).await
+    /// The clan rankings of a location, fetched page by page. Stops after `max_items` if given.
+    pub fn get_clan_rankings_stream(
+        &self,
+        location_id: u32,
+        max_items: Option<usize>,
+    ) -> impl Stream<Item = Result<ClanRanking, ApiError>> + '_ {
+        let span = info_span!("get_clan_rankings_stream", location_id);
+        let url = format!("{}/locations/{}/rankings/clans", BASE_URL, location_id);
+        self.paginate(span, Ok(url), Query::new(), max_items)
     }
 
-    pub async fn get_clan_warlog(&self, tag: String, config: ConfigForRezponse) -> Result<Rezponse<WarLog>, ApiError> {
-        let mut url = format!("https://api.clashofclans.com/v1/clans/{}/warlog", self.format_tag(tag));
-        url = self.get_cursor_url(url, config);
-        self.parse_json::<Rezponse<WarLog>>(self.get(url)).await
+    /// The player rankings of a location, fetched page by page. Stops after `max_items` if given.
+    pub fn get_player_rankings_stream(
+        &self,
+        location_id: u32,
+        max_items: Option<usize>,
+    ) -> impl Stream<Item = Result<PlayerRanking, ApiError>> + '_ {
+        let span = info_span!("get_player_rankings_stream", location_id);
+        let url = format!("{}/locations/{}/rankings/players", BASE_URL, location_id);
+        self.paginate(span, Ok(url), Query::new(), max_items)
     }
 
-    ///                                                            ///
-    ///                                                            ///
-    /// --------------------------END POINTS-----------------------///
-    ///                                                            ///
-    ///                                                            ///
+    //                                                            //
+    //                                                            //
+    // --------------------------END POINTS-----------------------//
+    //                                                            //
+    //                                                            //
+
+    /// Fetches one page, remembering the query so `next_page` and `prev_page` can replay it.
+    async fn get_page<T: DeserializeOwned>(&self, url: String, query: Query) -> Result<Page<T>, ApiError> {
+        let replay = query.clone().without_cursor().append_to(&url);
+        let page: Page<T> = self.parse_json(self.get(query.append_to(&url))).await?;
+        Ok(page.with_query(replay))
+    }
 
-    fn get_cursor_url(&self, mut url: String, config: ConfigForRezponse) -> String {
-        match config.limit {
-            Some(s) => {
-                url = format!("{}?limit={}", url, s);
-                match config.time {
-                    None => {
-                        url
+    /// Sends the stored query of a page again, with `cursor` appended.
+    async fn replay<T: DeserializeOwned>(&self, url: &str, cursor: Query) -> Result<Page<T>, ApiError> {
+        let page: Page<T> = self.parse_json(self.get(cursor.append_to(url))).await?;
+        Ok(page.with_query(url.to_string()))
+    }
+
+    /// Follows the `after` cursor of a paged endpoint until it runs out or `max_items`
+    /// have been yielded. The stream ends after the first error, including an invalid `url`.
+    /// Every page is fetched inside `span`, the span of the endpoint the stream belongs to.
+    fn paginate<'a, T>(
+        &'a self,
+        span: Span,
+        url: Result<String, ApiError>,
+        query: Query,
+        max_items: Option<usize>,
+    ) -> impl Stream<Item = Result<T, ApiError>> + 'a
+    where
+        T: DeserializeOwned + 'a,
+    {
+        let state = PageState {
+            after: None,
+            remaining: max_items,
+            done: false,
+        };
+        let page_size = query.get("limit").and_then(|limit| limit.parse::<u32>().ok());
+        let query = query.without_cursor();
+        stream::unfold(state, move |mut state| {
+            let mut query = query.clone();
+            let url = url.clone();
+            let span = span.clone();
+            async move {
+                if state.done || state.remaining == Some(0) {
+                    return None;
+                }
+                let url = match url {
+                    Ok(url) => url,
+                    Err(e) => {
+                        state.done = true;
+                        return Some((vec![Err(e)], state));
                     }
-                    Some(s1) => {
-                        match s1 {
-                            Time::After(a) => { format!("{}&after={}", url, a) }
-                            Time::Before(b) => { format!("{}&before={}", url, b) }
+                };
+                let remaining = state.remaining.map(|n| u32::try_from(n).unwrap_or(u32::MAX));
+                if let Some(limit) = remaining.into_iter().chain(page_size).min() {
+                    query = query.limit(limit);
+                }
+                if let Some(after) = state.after.take() {
+                    query = query.after(after);
+                }
+                let url = query.append_to(&url);
+                match self.parse_json::<Page<T>>(self.get(url)).await {
+                    Ok(page) => {
+                        state.after = page.next_cursor().map(str::to_string);
+                        let mut items = page.into_items();
+                        if let Some(remaining) = &mut state.remaining {
+                            items.truncate(*remaining);
+                            *remaining -= items.len();
                         }
+                        state.done = state.after.is_none() || items.is_empty();
+                        Some((items.into_iter().map(Ok).collect::<Vec<_>>(), state))
+                    }
+                    Err(e) => {
+                        state.done = true;
+                        Some((vec![Err(e)], state))
                     }
                 }
             }
-            None => {
-                match config.time {
-                    None => {
-                        url
+            .instrument(span)
+        })
+        .flat_map(stream::iter)
+    }
+
+    async fn parse_json<T: DeserializeOwned>(&self, req: Request) -> Result<T, ApiError> {
+        let cache_url = self.cache_url(&req);
+        if let (Some(cache), Some(url)) = (&self.cache, &cache_url) {
+            match cache.get(url) {
+                Some(entry) if !entry.is_expired() => match error::from_json(entry.body()) {
+                    Ok(parsed) => return Ok(parsed),
+                    // written by an older version of the models, fetch it again
+                    Err(_) => cache.remove(url),
+                },
+                Some(_) => cache.remove(url),
+                None => {}
+            }
+        }
+
+        if !self.maintenance.allow() {
+            return Err(ApiError::Maintenance);
+        }
+        let res = self.send_coalesced(req).await;
+        if let Ok(res) = &res {
+            if self.maintenance.record(maintenance::is_maintenance(res)) {
+                self.spawn_maintenance_probe();
+            }
+        }
+
+        match res {
+            Ok(res) if maintenance::is_maintenance(&res) => Err(ApiError::Maintenance),
+            Ok(res) => match res.status() {
+                StatusCode::OK => {
+                    let max_age = cache::max_age(res.headers());
+                    let t = res.into_body();
+                    trace!(body = %t, "response body");
+                    let parsed = error::from_json(t.as_str())?;
+                    if let (Some(cache), Some(url), Some(max_age)) = (&self.cache, cache_url, max_age) {
+                        cache.insert(&url, CacheEntry::new(t, max_age));
                     }
-                    Some(s) => {
-                        match s {
-                            Time::After(a) => { format!("{}?after={}", url, a) }
-                            Time::Before(b) => { format!("{}?before={}", url, b) }
-                        }
+                    Ok(parsed)
+                }
+                _ => Err(ApiError::from_response(&res)),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Probes the API every probe interval until maintenance is over, so subscribers
+    /// are told even when no other request is sent.
+    fn spawn_maintenance_probe(&self) {
+        let client = self.clone();
+        tokio::spawn(async move {
+            while client.maintenance.in_maintenance() {
+                tokio::time::sleep(client.maintenance.probe_interval()).await;
+                // a caller's request may be probing already
+                if !client.maintenance.allow() {
+                    continue;
+                }
+                let req = client.get(format!("{}/goldpass/seasons/current", BASE_URL));
+                match client.send(req).await {
+                    Ok(res) => {
+                        client.maintenance.record(maintenance::is_maintenance(&res));
+                    }
+                    Err(e) => {
+                        debug!(error = %e, "maintenance probe failed");
+                        client.maintenance.record(true);
                     }
                 }
             }
+        });
+    }
+
+    /// The cache key for `req`, if its response may be served from or stored in the cache.
+    fn cache_url(&self, req: &Request) -> Option<String> {
+        if self.cache.is_some() && self.use_cache && req.method() == Method::GET {
+            Some(req.url().to_string())
+        } else {
+            None
         }
     }
 
+    /// Sends `req`, sharing the result with identical GETs that are already in flight.
+    async fn send_coalesced(&self, req: Request) -> Result<Response, ApiError> {
+        if req.method() != Method::GET {
+            return self.send(req).await;
+        }
 
-    fn format_tag(&self, tag: String) -> String {
-        return if tag[0..1].contains("#") {
-            tag.replace("#", "%23")
-        } else {
-            format!("%23{}", tag)
+        let url = req.url().to_string();
+        let fut = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            match in_flight.get(&url) {
+                Some(fut) if fut.peek().is_none() => {
+                    trace!(url = %url, "joining in-flight request");
+                    fut.clone()
+                }
+                _ => {
+                    // the future is stored in `in_flight`, so it must not hold on to it
+                    let client = Self {
+                        in_flight: Arc::default(),
+                        ..self.clone()
+                    };
+                    let fut = async move { client.send(req).await }
+                        .boxed()
+                        .shared();
+                    in_flight.insert(url.clone(), fut.clone());
+                    fut
+                }
+            }
         };
+
+        let result = fut.clone().await;
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight.get(&url).is_some_and(|current| current.ptr_eq(&fut)) {
+            in_flight.remove(&url);
+        }
+        result
     }
 
-    fn is_valid_tag(&self, tag: String) -> bool {
-        Regex::new("^#[PYLQGRJCUV0289]+$").unwrap()
-            .is_match(
-                tag.to_uppercase()
-                    .replace("O", "0")
-                    .as_str()
-            )
+    #[instrument(
+        name = "request",
+        skip_all,
+        fields(method = %req.method(), url = %req.url(), status, retries = 0, latency_ms)
+    )]
+    async fn send(&self, req: Request) -> Result<Response, ApiError> {
+        let start = Instant::now();
+        let result = self.send_with_retries(req).await;
+        let span = Span::current();
+        span.record("latency_ms", start.elapsed().as_millis() as u64);
+        if let Ok(res) = &result {
+            span.record("status", res.status().as_u16());
+        }
+        result
     }
 
-    fn fix_tag(&self, tag: String) -> String {
-        let re = Regex::new("[^A-Z0-9]+").unwrap();
-        "#".to_owned()
-            + &re
-            .replace_all(tag.to_uppercase().as_str(), "")
-            .replace("O", "0")
+    async fn send_with_retries(&self, req: Request) -> Result<Response, ApiError> {
+        let policy = match &self.retry_policy {
+            Some(policy) if req.method().is_idempotent() || policy.retries_non_idempotent() => policy,
+            _ => return self.execute(req).await,
+        };
+
+        let mut attempt = 1;
+        loop {
+            let result = self.execute(req.clone()).await;
+            if attempt >= policy.max_attempts() {
+                return result;
+            }
+            let delay = match &result {
+                Ok(res) if maintenance::is_maintenance(res) => return result,
+                Ok(res) if policy.retries_status(res.status()) => match retry::retry_after(res.headers()) {
+                    // waiting longer than the policy allows, hand the hint to the caller instead
+                    Some(delay) if delay > policy.max_delay() => return result,
+                    Some(delay) => delay,
+                    None => policy.backoff(attempt),
+                },
+                Err(ApiError::Timeout(e) | ApiError::Network(e)) if policy.retries_error(e) => {
+                    policy.backoff(attempt)
+                }
+                _ => return result,
+            };
+            debug!(attempt, delay_ms = delay.as_millis() as u64, "retrying request");
+            tokio::time::sleep(delay).await;
+            Span::current().record("retries", attempt);
+            attempt += 1;
+        }
     }
 
-    async fn parse_json<T: DeserializeOwned>(
-        &self,
-        rb: Result<RequestBuilder, reqwest::Error>,
-    ) -> Result<T, ApiError> {
-        match rb {
-            Ok(rb) => match rb.send().await {
-                Ok(res) => match res.status() {
-                    reqwest::StatusCode::OK => {
-                        let t = res
-                            .json()
-                            .await
-                            .expect("Unexpected json response from the API, cannot parse json");
-                        //println!("{}", &t);
-                        Ok(serde_json::from_str(t.as_str()).unwrap())
+    /// Sends a single attempt of `req` through the middleware chain.
+    #[instrument(name = "attempt", skip_all, fields(key_id, status, latency_ms))]
+    async fn execute(&self, mut req: Request) -> Result<Response, ApiError> {
+        let canned = self
+            .middleware
+            .iter()
+            .find_map(|middleware| middleware.before_request(&mut req));
+        let result = match canned {
+            Some(res) => Ok(res),
+            None => self.send_authorized(&req).await,
+        };
+        match result {
+            Ok(mut res) => {
+                for middleware in self.middleware.iter().rev() {
+                    middleware.after_response(&req, &mut res);
+                }
+                Ok(res)
+            }
+            Err(e) => {
+                for middleware in self.middleware.iter().rev() {
+                    middleware.on_error(&req, &e);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Sends `req` with the next API key through the key's egress.
+    async fn send_authorized(&self, req: &Request) -> Result<Response, ApiError> {
+        let key = self.next_key().await?;
+        let span = Span::current();
+        span.record("key_id", key.id.as_str());
+        let start = Instant::now();
+        let mut auth = HeaderValue::from_str(&format!("Bearer {}", key.key)).map_err(|e| {
+            TransportError::new(TransportErrorKind::Other, "invalid API key").with_source(e)
+        })?;
+        auth.set_sensitive(true);
+        let mut authed = req.clone();
+        authed.headers_mut().insert(AUTHORIZATION, auth);
+        let res = self.egress[key.egress].send(authed).await?;
+        span.record("status", res.status().as_u16());
+        span.record("latency_ms", start.elapsed().as_millis() as u64);
+        Ok(res)
+    }
+
+    async fn next_key(&self) -> Result<ApiKey, ApiError> {
+        match &self.rate_limiter {
+            None => self.cycle(),
+            Some(limiter) => {
+                let keys = {
+                    let mut keys = self.keys.lock().unwrap();
+                    if keys.is_empty() {
+                        return Err(ApiError::NoKeys);
                     }
-                    _ => Err(ApiError::Api(res.status())),
-                },
-                Err(e) => Err(ApiError::Request(e)),
-            },
-            Err(e) => return Err(ApiError::Request(e)),
+                    keys.rotate_left(1);
+                    keys.clone()
+                };
+                let ids: Vec<&str> = keys.iter().map(|key| key.id.as_str()).collect();
+                let i = limiter.acquire(&ids, self.priority).await;
+                Ok(keys[i].clone())
+            }
+        }
+    }
+
+    fn cycle(&self) -> Result<ApiKey, ApiError> {
+        let mut keys = self.keys.lock().unwrap();
+        if keys.is_empty() {
+            return Err(ApiError::NoKeys);
         }
+        keys.rotate_left(1);
+        Ok(keys[0].clone())
     }
+}
 
-    fn cycle(&self) -> String {
-        TOKEN_LIST.lock().unwrap().rotate_left(1);
-        TOKEN_LIST.lock().unwrap().get(0).unwrap().to_string().clone()
+/// A developer portal key usable from one of the client's egresses. Only `id` may be logged.
+#[derive(Clone)]
+pub(crate) struct ApiKey {
+    pub(crate) id: String,
+    pub(crate) key: String,
+    pub(crate) egress: usize,
+}
+
+impl std::fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKey")
+            .field("id", &self.id)
+            .field("egress", &self.egress)
+            .finish_non_exhaustive()
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Paging {
-    #[serde(rename = "cursors")]
-    cursor: Cursor,
+#[derive(Debug, Serialize)]
+struct VerifyToken {
+    token: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Cursor {
-    before: Option<String>,
+/// Where `Client::paginate` is in a paged endpoint.
+struct PageState {
     after: Option<String>,
+    remaining: Option<usize>,
+    done: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Rezponse<T> {
-    #[serde(rename = "items")]
-    items: Vec<T>,
-    #[serde(rename = "paging")]
-    paging: Paging,
+/// Matches every key to the first egress whose public IP it is registered for, `None`
+/// being an egress whose IP is unknown. Keys registered for none of them are dropped.
+#[cfg(feature = "dev")]
+pub(crate) fn match_keys(keys: &[Key], egress_ips: &[Option<String>]) -> Vec<ApiKey> {
+    let mut matched: Vec<ApiKey> = Vec::new();
+    for (egress, ip) in egress_ips.iter().enumerate() {
+        let Some(ip) = ip else { continue };
+        for key in keys {
+            if key.cidr_ranges().contains(ip) && !matched.iter().any(|k| k.id == key.id()) {
+                matched.push(ApiKey {
+                    id: key.id().to_string(),
+                    key: key.key().to_string(),
+                    egress,
+                })
+            }
+        }
+    }
+    matched
 }
 
-#[derive(Debug)]
-pub struct ConfigForRezponse {
-    pub limit: Option<u32>,
-    pub time: Option<Time>,
+/// Parses a tag of a bulk request along with the key its result is reported under:
+/// the normalized tag, or the raw input if it is not a valid tag.
+fn parse_bulk_tag<T>(tag: T) -> (String, Result<Tag, ApiError>)
+where
+    T: TryInto<Tag> + ToString,
+    ApiError: From<T::Error>,
+{
+    let raw = tag.to_string();
+    match tag.try_into() {
+        Ok(tag) => (tag.to_string(), Ok(tag)),
+        Err(e) => (raw, Err(e.into())),
+    }
 }
 
-#[derive(Debug)]
-pub enum Time {
-    After(String),
-    Before(String),
-}
\ No newline at end of file
+/// Validates an endpoint's tag argument and records it on the endpoint's span.
+fn parse_tag<T>(tag: T) -> Result<Tag, ApiError>
+where
+    T: TryInto<Tag>,
+    ApiError: From<T::Error>,
+{
+    let tag = tag.try_into()?;
+    Span::current().record("tag", tag.as_str());
+    Ok(tag)
+}
@@ -0,0 +1,226 @@
+use futures::{StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::api::{ApiError, Client};
+use crate::http::HttpTransport;
+use crate::models::clan::{Clan, ClanMember};
+use crate::models::current_war::War;
+use crate::models::gold_pass::GoldPass;
+use crate::models::paging::Page;
+use crate::models::ranking::{ClanRanking, PlayerRanking};
+use crate::models::tag::Tag;
+use crate::models::player::{Player, PlayerToken};
+use crate::models::war_log::WarLog;
+use crate::query::{ClanSearch, Query};
+
+/// A synchronous wrapper around `Client` that drives requests on its own runtime.
+///
+/// Its methods block the calling thread and must not be called from within an async runtime.
+#[derive(Debug)]
+pub struct BlockingClient {
+    inner: Client,
+    runtime: Runtime,
+}
+
+impl BlockingClient {
+    #[cfg(feature = "dev")]
+    pub fn new(username: String, password: String) -> Result<Self, ApiError> {
+        let runtime = Self::runtime();
+        let inner = runtime.block_on(Client::new(username, password))?;
+        Ok(Self { inner, runtime })
+    }
+
+    #[cfg(feature = "dev")]
+    pub fn with_proxies(
+        username: String,
+        password: String,
+        proxies: Vec<String>,
+    ) -> Result<Self, ApiError> {
+        let runtime = Self::runtime();
+        let inner = runtime.block_on(Client::with_proxies(username, password, proxies))?;
+        Ok(Self { inner, runtime })
+    }
+
+    #[cfg(feature = "reqwest")]
+    pub fn from_keys(keys: Vec<String>) -> Self {
+        Self::from_client(Client::from_keys(keys))
+    }
+
+    pub fn with_transport(transport: impl HttpTransport + 'static, keys: Vec<String>) -> Self {
+        Self::from_client(Client::with_transport(transport, keys))
+    }
+
+    /// Wraps an already configured client.
+    pub fn from_client(client: Client) -> Self {
+        Self {
+            inner: client,
+            runtime: Self::runtime(),
+        }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.inner
+    }
+
+    fn runtime() -> Runtime {
+        Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to start the blocking client runtime")
+    }
+
+    pub fn get_clan<T>(&self, tag: T) -> Result<Clan, ApiError>
+    where
+        T: TryInto<Tag>,
+        ApiError: From<T::Error>,
+    {
+        self.runtime.block_on(self.inner.get_clan(tag))
+    }
+
+    pub fn get_player<T>(&self, tag: T) -> Result<Player, ApiError>
+    where
+        T: TryInto<Tag>,
+        ApiError: From<T::Error>,
+    {
+        self.runtime.block_on(self.inner.get_player(tag))
+    }
+
+    pub fn get_players<T>(&self, tags: Vec<T>) -> Vec<(String, Result<Player, ApiError>)>
+    where
+        T: TryInto<Tag> + ToString,
+        ApiError: From<T::Error>,
+    {
+        self.runtime.block_on(self.inner.get_players(tags).collect())
+    }
+
+    pub fn get_clans<T>(&self, tags: Vec<T>) -> Vec<(String, Result<Clan, ApiError>)>
+    where
+        T: TryInto<Tag> + ToString,
+        ApiError: From<T::Error>,
+    {
+        self.runtime.block_on(self.inner.get_clans(tags).collect())
+    }
+
+    pub fn get_current_war<T>(&self, tag: T) -> Result<War, ApiError>
+    where
+        T: TryInto<Tag>,
+        ApiError: From<T::Error>,
+    {
+        self.runtime.block_on(self.inner.get_current_war(tag))
+    }
+
+    pub fn get_goldpass(&self) -> Result<GoldPass, ApiError> {
+        self.runtime.block_on(self.inner.get_goldpass())
+    }
+
+    pub fn get_verified_player<T>(&self, tag: T, token: String) -> Result<PlayerToken, ApiError>
+    where
+        T: TryInto<Tag>,
+        ApiError: From<T::Error>,
+    {
+        self.runtime.block_on(self.inner.get_verified_player(tag, token))
+    }
+
+    pub fn get_clan_members<T>(
+        &self,
+        tag: T,
+        query: Query,
+    ) -> Result<Page<ClanMember>, ApiError>
+    where
+        T: TryInto<Tag>,
+        ApiError: From<T::Error>,
+    {
+        self.runtime.block_on(self.inner.get_clan_members(tag, query))
+    }
+
+    pub fn get_clan_warlog<T>(
+        &self,
+        tag: T,
+        query: Query,
+    ) -> Result<Page<WarLog>, ApiError>
+    where
+        T: TryInto<Tag>,
+        ApiError: From<T::Error>,
+    {
+        self.runtime.block_on(self.inner.get_clan_warlog(tag, query))
+    }
+
+    pub fn search_clans(&self, search: ClanSearch) -> Result<Page<Clan>, ApiError> {
+        self.runtime.block_on(self.inner.search_clans(search))
+    }
+
+    pub fn get_clan_rankings(&self, location_id: u32, query: Query) -> Result<Page<ClanRanking>, ApiError> {
+        self.runtime.block_on(self.inner.get_clan_rankings(location_id, query))
+    }
+
+    pub fn get_player_rankings(&self, location_id: u32, query: Query) -> Result<Page<PlayerRanking>, ApiError> {
+        self.runtime.block_on(self.inner.get_player_rankings(location_id, query))
+    }
+
+    pub fn next_page<T: DeserializeOwned>(&self, page: &Page<T>) -> Result<Option<Page<T>>, ApiError> {
+        self.runtime.block_on(self.inner.next_page(page))
+    }
+
+    pub fn prev_page<T: DeserializeOwned>(&self, page: &Page<T>) -> Result<Option<Page<T>>, ApiError> {
+        self.runtime.block_on(self.inner.prev_page(page))
+    }
+
+    /// Collects every page of clan members, stopping at the first error.
+    pub fn get_all_clan_members<T>(
+        &self,
+        tag: T,
+        max_items: Option<usize>,
+    ) -> Result<Vec<ClanMember>, ApiError>
+    where
+        T: TryInto<Tag>,
+        ApiError: From<T::Error>,
+    {
+        self.runtime
+            .block_on(self.inner.get_clan_members_stream(tag, max_items).try_collect())
+    }
+
+    /// Collects every page of the war log, stopping at the first error.
+    pub fn get_all_clan_warlog<T>(
+        &self,
+        tag: T,
+        max_items: Option<usize>,
+    ) -> Result<Vec<WarLog>, ApiError>
+    where
+        T: TryInto<Tag>,
+        ApiError: From<T::Error>,
+    {
+        self.runtime
+            .block_on(self.inner.get_clan_warlog_stream(tag, max_items).try_collect())
+    }
+
+    /// Collects every clan matching `search`, stopping at the first error.
+    pub fn search_all_clans(
+        &self,
+        search: ClanSearch,
+        max_items: Option<usize>,
+    ) -> Result<Vec<Clan>, ApiError> {
+        self.runtime
+            .block_on(self.inner.search_clans_stream(search, max_items).try_collect())
+    }
+
+    /// Collects every page of a location's clan rankings, stopping at the first error.
+    pub fn get_all_clan_rankings(
+        &self,
+        location_id: u32,
+        max_items: Option<usize>,
+    ) -> Result<Vec<ClanRanking>, ApiError> {
+        self.runtime
+            .block_on(self.inner.get_clan_rankings_stream(location_id, max_items).try_collect())
+    }
+
+    /// Collects every page of a location's player rankings, stopping at the first error.
+    pub fn get_all_player_rankings(
+        &self,
+        location_id: u32,
+        max_items: Option<usize>,
+    ) -> Result<Vec<PlayerRanking>, ApiError> {
+        self.runtime
+            .block_on(self.inner.get_player_rankings_stream(location_id, max_items).try_collect())
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use http::header::{HeaderMap, CACHE_CONTROL};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "extras")]
+mod disk;
+#[cfg(feature = "extras")]
+pub use disk::DiskCache;
+
+/// A raw response body together with the time it stops being fresh.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    body: String,
+    expires_at: SystemTime,
+}
+
+impl CacheEntry {
+    pub fn new(body: String, max_age: Duration) -> Self {
+        Self {
+            body,
+            expires_at: SystemTime::now() + max_age,
+        }
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+    pub fn into_body(self) -> String {
+        self.body
+    }
+    pub fn expires_at(&self) -> SystemTime {
+        self.expires_at
+    }
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= SystemTime::now()
+    }
+}
+
+/// Storage for cached responses, keyed by endpoint url.
+///
+/// Backends may return expired entries; the client checks `CacheEntry::is_expired`
+/// and removes them.
+pub trait CacheBackend: Debug + Send + Sync {
+    fn get(&self, url: &str) -> Option<CacheEntry>;
+    fn insert(&self, url: &str, entry: CacheEntry);
+    fn remove(&self, url: &str);
+    fn clear(&self);
+}
+
+#[derive(Debug, Default)]
+struct LruState {
+    entries: HashMap<String, (CacheEntry, u64)>,
+    order: BTreeMap<u64, String>,
+    tick: u64,
+    bytes: usize,
+}
+
+impl LruState {
+    fn remove(&mut self, url: &str) {
+        if let Some((entry, tick)) = self.entries.remove(url) {
+            self.order.remove(&tick);
+            self.bytes -= url.len() + entry.body.len();
+        }
+    }
+
+    fn touch(&mut self, url: &str) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some((_, old)) = self.entries.get_mut(url) {
+            self.order.remove(old);
+            *old = tick;
+            self.order.insert(tick, url.to_string());
+        }
+    }
+
+    fn pop_oldest(&mut self) {
+        if let Some(url) = self.order.values().next().cloned() {
+            self.remove(&url);
+        }
+    }
+}
+
+/// In-memory least-recently-used cache bounded by entry count and, optionally, total bytes.
+#[derive(Debug)]
+pub struct MemoryCache {
+    max_entries: usize,
+    max_bytes: Option<usize>,
+    state: Mutex<LruState>,
+}
+
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+impl MemoryCache {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries: max_entries.max(1),
+            max_bytes: None,
+            state: Mutex::new(LruState::default()),
+        }
+    }
+
+    /// Bounds the summed size of urls and bodies held by the cache.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl CacheBackend for MemoryCache {
+    fn get(&self, url: &str) -> Option<CacheEntry> {
+        let mut state = self.state.lock().unwrap();
+        let entry = state.entries.get(url)?.0.clone();
+        state.touch(url);
+        Some(entry)
+    }
+
+    fn insert(&self, url: &str, entry: CacheEntry) {
+        let size = url.len() + entry.body.len();
+        if self.max_bytes.is_some_and(|max| size > max) {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.remove(url);
+        while state.entries.len() >= self.max_entries
+            || self.max_bytes.is_some_and(|max| state.bytes + size > max)
+        {
+            state.pop_oldest();
+        }
+        state.tick += 1;
+        let tick = state.tick;
+        state.bytes += size;
+        state.order.insert(tick, url.to_string());
+        state.entries.insert(url.to_string(), (entry, tick));
+    }
+
+    fn remove(&self, url: &str) {
+        self.state.lock().unwrap().remove(url);
+    }
+
+    fn clear(&self) {
+        *self.state.lock().unwrap() = LruState::default();
+    }
+}
+
+/// Reads `max-age` from a `Cache-Control` header. Responses marked `no-store`
+/// or `no-cache`, or without a positive max-age, are not cacheable.
+pub(crate) fn max_age(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(CACHE_CONTROL)?.to_str().ok()?;
+    let mut max_age = None;
+    for directive in value.split(',').map(str::trim) {
+        let directive = directive.to_ascii_lowercase();
+        if directive == "no-store" || directive == "no-cache" {
+            return None;
+        }
+        if let Some(secs) = directive.strip_prefix("max-age=") {
+            max_age = secs.trim_matches('"').parse::<u64>().ok();
+        }
+    }
+    max_age.filter(|secs| *secs > 0).map(Duration::from_secs)
+}
@@ -0,0 +1,161 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{CacheBackend, CacheEntry};
+
+/// Makes temp file names unique between concurrent writes of the same url in one process.
+static WRITE_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Entries are named `coc-<hash>.cache.json` and temp files `coc-<hash>.<pid>-<n>.tmp`, so
+/// that only files written by the cache are ever deleted from a shared directory.
+const PREFIX: &str = "coc-";
+const ENTRY_SUFFIX: &str = ".cache.json";
+const TMP_SUFFIX: &str = ".tmp";
+
+/// Temp files older than this were left behind by a crashed write.
+const STALE_TMP: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DiskEntry {
+    url: String,
+    #[serde(flatten)]
+    entry: CacheEntry,
+}
+
+/// Cache persisted as one json file per url, so responses survive restarts and
+/// can be shared between processes using the same directory. Other files in the
+/// directory are left alone.
+#[derive(Debug)]
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Deletes every expired or unreadable entry, and temp files left behind by crashed writes.
+    pub fn purge_expired(&self) -> io::Result<()> {
+        for file in fs::read_dir(&self.dir)? {
+            let file = file?;
+            let name = file.file_name();
+            let name = name.to_string_lossy();
+            let path = file.path();
+            if is_entry(&name) {
+                let expired = fs::read(&path)
+                    .ok()
+                    .and_then(|data| serde_json::from_slice::<DiskEntry>(&data).ok())
+                    .is_none_or(|e| e.entry.is_expired());
+                if expired {
+                    let _ = fs::remove_file(path);
+                }
+            } else if is_tmp(&name) {
+                // another process may still be writing a recent one
+                let stale = file
+                    .metadata()
+                    .and_then(|meta| meta.modified())
+                    .ok()
+                    .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                    .is_some_and(|age| age >= STALE_TMP);
+                if stale {
+                    let _ = fs::remove_file(path);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{}{:016x}{}", PREFIX, hash(url), ENTRY_SUFFIX))
+    }
+
+    fn tmp_path(&self, url: &str) -> PathBuf {
+        let write_id = WRITE_ID.fetch_add(1, Ordering::Relaxed);
+        self.dir.join(format!(
+            "{}{:016x}.{}-{}{}",
+            PREFIX,
+            hash(url),
+            std::process::id(),
+            write_id,
+            TMP_SUFFIX
+        ))
+    }
+}
+
+/// FNV-1a, stable across builds unlike std's DefaultHasher.
+fn hash(url: &str) -> u64 {
+    url.bytes().fold(0xcbf29ce484222325u64, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn is_hash(s: &str) -> bool {
+    s.len() == 16 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn is_entry(name: &str) -> bool {
+    name.strip_prefix(PREFIX)
+        .and_then(|name| name.strip_suffix(ENTRY_SUFFIX))
+        .is_some_and(is_hash)
+}
+
+fn is_tmp(name: &str) -> bool {
+    name.strip_prefix(PREFIX)
+        .and_then(|name| name.strip_suffix(TMP_SUFFIX))
+        .and_then(|name| name.split_once('.'))
+        .is_some_and(|(hash, _)| is_hash(hash))
+}
+
+impl CacheBackend for DiskCache {
+    fn get(&self, url: &str) -> Option<CacheEntry> {
+        let data = fs::read(self.path(url)).ok()?;
+        let disk: DiskEntry = serde_json::from_slice(&data).ok()?;
+        if disk.url == url {
+            Some(disk.entry)
+        } else {
+            None
+        }
+    }
+
+    fn insert(&self, url: &str, entry: CacheEntry) {
+        let path = self.path(url);
+        let tmp = self.tmp_path(url);
+        let disk = DiskEntry {
+            url: url.to_string(),
+            entry,
+        };
+        let written = serde_json::to_vec(&disk)
+            .map_err(io::Error::from)
+            .and_then(|data| fs::write(&tmp, data))
+            .and_then(|_| fs::rename(&tmp, &path));
+        if written.is_err() {
+            let _ = fs::remove_file(tmp);
+        }
+    }
+
+    fn remove(&self, url: &str) {
+        let _ = fs::remove_file(self.path(url));
+    }
+
+    fn clear(&self) {
+        if let Ok(files) = fs::read_dir(&self.dir) {
+            for file in files.flatten() {
+                if is_entry(&file.file_name().to_string_lossy()) {
+                    let _ = fs::remove_file(file.path());
+                }
+            }
+        }
+    }
+}
@@ -1,25 +1,48 @@
-use reqwest::{Error, RequestBuilder, Response};
 use serde::{ Deserialize, Serialize };
-use serde::de::StdError;
-use crate::api::Client;
+use crate::dev_models::existing_key::{ExistingKeys, Status};
+use crate::error::{self, ApiError};
+use crate::http::{HttpTransport, Request, TransportError, TransportErrorKind};
+use http::Method;
+use tracing::{instrument, Span};
 
 // manage a session
 pub const BASE_DEV_URL: &str = "https://developer.clashofclans.com/api";
 const IP_URL: &str = "https://api.ipify.org";
 
-pub async fn login(email: String, password: String) -> reqwest::Client {
+#[instrument(skip_all)]
+pub async fn login(email: String, password: String) -> Result<reqwest::Client, ApiError> {
     let client = reqwest::Client::builder()
         .cookie_store(true)
         .build()
-        .unwrap();
-    client.post(format!("{}/login", BASE_DEV_URL))
+        .map_err(TransportError::from)?;
+    let res = client.post(format!("{}/login", BASE_DEV_URL))
         .json::<Creds>(&Creds {
             email,
             password,
         })
         .send()
-        .await.expect("TODO: panic message");
-    client
+        .await
+        .map_err(TransportError::from)?;
+    portal_body(res).await?;
+    Ok(client)
+}
+
+/// The body of a developer portal response, or `ApiError::Login` if the portal refused the request.
+async fn portal_body(res: reqwest::Response) -> Result<String, ApiError> {
+    let status = res.status();
+    let body = res.text().await.map_err(TransportError::from)?;
+    if !status.is_success() {
+        let message = serde_json::from_str::<PortalResponse>(&body)
+            .ok()
+            .map(|res| res.status.message().to_string());
+        return Err(ApiError::Login { status, message });
+    }
+    Ok(body)
+}
+
+#[derive(Debug, Deserialize)]
+struct PortalResponse {
+    status: Status,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -28,20 +51,30 @@ pub struct Creds {
     pub password: String,
 }
 
-pub async fn get_ip() -> Result<String, reqwest::Error> {
-    let res = reqwest::Client::new().get(IP_URL).send().await?;
-    let ip = res.text().await?;
+/// Public IP of the egress `transport` sends its requests through.
+#[instrument(skip_all, fields(ip))]
+pub async fn get_ip(transport: &dyn HttpTransport) -> Result<String, TransportError> {
+    let res = transport.send(Request::new(Method::GET, IP_URL.to_string())).await?;
+    if !res.status().is_success() {
+        return Err(TransportError::new(
+            TransportErrorKind::Other,
+            format!("ip lookup failed with status {}", res.status()),
+        ));
+    }
+    let ip = res.body().trim().to_string();
+    Span::current().record("ip", ip.as_str());
     Ok(ip)
 }
 
 
-pub async fn get_keys(username: String, password: String) -> crate::dev_models::existing_key::ExistingKeys {
-    let client = login(username, password).await;
-    client.post(format!("{}/apikey/list", BASE_DEV_URL))
+#[instrument(skip_all, fields(keys))]
+pub async fn get_keys(username: String, password: String) -> Result<ExistingKeys, ApiError> {
+    let client = login(username, password).await?;
+    let res = client.post(format!("{}/apikey/list", BASE_DEV_URL))
         .send()
         .await
-        .unwrap()
-        .json()
-        .await
-        .unwrap()
-}
\ No newline at end of file
+        .map_err(TransportError::from)?;
+    let keys: ExistingKeys = error::from_json(&portal_body(res).await?)?;
+    Span::current().record("keys", keys.keys().len());
+    Ok(keys)
+}
@@ -0,0 +1,330 @@
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::StatusCode;
+use serde::de::DeserializeOwned;
+
+use crate::http::{Response, TransportError, TransportErrorKind};
+use crate::models::client_error::ClientError;
+use crate::models::tag::TagError;
+use crate::retry;
+
+#[derive(Debug, Clone)]
+pub enum ApiError {
+    /// 404, the clan, player or resource does not exist.
+    NotFound(Option<ClientError>),
+    /// 403 for a reason other than the key's IP or a private war log.
+    AccessDenied(Option<ClientError>),
+    /// 403 `accessDenied.invalidIp`, the key is not registered for this IP.
+    InvalidIp(Option<ClientError>),
+    /// 403 because the clan's war log is private.
+    PrivateWarLog(Option<ClientError>),
+    /// 429, with the server's `Retry-After` hint if it sent one.
+    Throttled {
+        retry_after: Option<Duration>,
+        error: Option<ClientError>,
+    },
+    /// The API is down for game maintenance; requests fail fast until it is over.
+    Maintenance,
+    /// 400, the request parameters were rejected.
+    BadRequest(Option<ClientError>),
+    Timeout(TransportError),
+    /// The request could not be sent or its response could not be read.
+    Network(TransportError),
+    /// The response body did not match the model.
+    Deserialize(DeserializeError),
+    /// Any other error status.
+    Unknown(StatusCode, Option<ClientError>),
+    /// A malformed tag was rejected before sending the request.
+    InvalidTag(TagError),
+    /// The client has no API key to send the request with.
+    NoKeys,
+    /// The developer portal refused the login or the key listing, e.g. for a wrong password.
+    Login {
+        status: StatusCode,
+        message: Option<String>,
+    },
+}
+
+impl ApiError {
+    /// Classifies an error response.
+    pub(crate) fn from_response(res: &Response) -> Self {
+        let error: Option<ClientError> = serde_json::from_str(res.body()).ok();
+        let reason = error.as_ref().map(ClientError::reason);
+        match res.status() {
+            StatusCode::BAD_REQUEST => ApiError::BadRequest(error),
+            StatusCode::NOT_FOUND => ApiError::NotFound(error),
+            StatusCode::FORBIDDEN => match reason {
+                Some("accessDenied.invalidIp") => ApiError::InvalidIp(error),
+                Some("privateWarLog") => ApiError::PrivateWarLog(error),
+                Some("accessDenied") if error.as_ref().is_some_and(mentions_private_war_log) => {
+                    ApiError::PrivateWarLog(error)
+                }
+                _ => ApiError::AccessDenied(error),
+            },
+            StatusCode::TOO_MANY_REQUESTS => ApiError::Throttled {
+                retry_after: retry::retry_after(res.headers()),
+                error,
+            },
+            StatusCode::SERVICE_UNAVAILABLE if reason == Some("inMaintenance") => ApiError::Maintenance,
+            status => ApiError::Unknown(status, error),
+        }
+    }
+
+    /// The HTTP status the API answered with, if it answered.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            ApiError::NotFound(_) => Some(StatusCode::NOT_FOUND),
+            ApiError::AccessDenied(_) | ApiError::InvalidIp(_) | ApiError::PrivateWarLog(_) => {
+                Some(StatusCode::FORBIDDEN)
+            }
+            ApiError::Throttled { .. } => Some(StatusCode::TOO_MANY_REQUESTS),
+            ApiError::Maintenance => Some(StatusCode::SERVICE_UNAVAILABLE),
+            ApiError::BadRequest(_) => Some(StatusCode::BAD_REQUEST),
+            ApiError::Unknown(status, _) | ApiError::Login { status, .. } => Some(*status),
+            ApiError::Timeout(_)
+            | ApiError::Network(_)
+            | ApiError::Deserialize(_)
+            | ApiError::InvalidTag(_)
+            | ApiError::NoKeys => None,
+        }
+    }
+
+    /// The error body sent by the API, if any.
+    pub fn client_error(&self) -> Option<&ClientError> {
+        match self {
+            ApiError::NotFound(error)
+            | ApiError::AccessDenied(error)
+            | ApiError::InvalidIp(error)
+            | ApiError::PrivateWarLog(error)
+            | ApiError::Throttled { error, .. }
+            | ApiError::BadRequest(error)
+            | ApiError::Unknown(_, error) => error.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The `reason` of the API error body, e.g. `notFound` or `accessDenied.invalidIp`.
+    pub fn reason(&self) -> Option<&str> {
+        self.client_error().map(ClientError::reason)
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ApiError::Throttled { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Whether sending the same request again later may succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ApiError::Throttled { .. } | ApiError::Timeout(_) => true,
+            ApiError::Network(e) => e.kind() != TransportErrorKind::Other,
+            ApiError::Unknown(status, _) => status.is_server_error(),
+            _ => false,
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, ApiError::NotFound(_))
+    }
+
+    /// Any 403, including invalid IPs and private war logs.
+    pub fn is_access_denied(&self) -> bool {
+        matches!(
+            self,
+            ApiError::AccessDenied(_) | ApiError::InvalidIp(_) | ApiError::PrivateWarLog(_)
+        )
+    }
+
+    pub fn is_invalid_ip(&self) -> bool {
+        matches!(self, ApiError::InvalidIp(_))
+    }
+
+    pub fn is_private_war_log(&self) -> bool {
+        matches!(self, ApiError::PrivateWarLog(_))
+    }
+
+    pub fn is_throttled(&self) -> bool {
+        matches!(self, ApiError::Throttled { .. })
+    }
+
+    pub fn is_maintenance(&self) -> bool {
+        matches!(self, ApiError::Maintenance)
+    }
+
+    pub fn is_bad_request(&self) -> bool {
+        matches!(self, ApiError::BadRequest(_))
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, ApiError::Timeout(_))
+    }
+
+    pub fn is_network(&self) -> bool {
+        matches!(self, ApiError::Network(_))
+    }
+
+    pub fn is_deserialize(&self) -> bool {
+        matches!(self, ApiError::Deserialize(_))
+    }
+
+    pub fn is_invalid_tag(&self) -> bool {
+        matches!(self, ApiError::InvalidTag(_))
+    }
+
+    pub fn is_no_keys(&self) -> bool {
+        matches!(self, ApiError::NoKeys)
+    }
+
+    pub fn is_login(&self) -> bool {
+        matches!(self, ApiError::Login { .. })
+    }
+}
+
+/// A response body that does not match its model, usually because the API changed.
+#[derive(Debug, Clone)]
+pub struct DeserializeError {
+    path: String,
+    snippet: String,
+    source: Arc<serde_json::Error>,
+}
+
+/// How much of the raw body is kept for error reports.
+const SNIPPET_LEN: usize = 256;
+
+impl DeserializeError {
+    /// JSON path of the field that failed, e.g. `memberList[3].league.id`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The start of the raw body, cut to a few hundred bytes.
+    pub fn snippet(&self) -> &str {
+        &self.snippet
+    }
+}
+
+/// Deserializes `body`, reporting where it stopped matching `T` instead of panicking.
+pub(crate) fn from_json<T: DeserializeOwned>(body: &str) -> Result<T, DeserializeError> {
+    let de = &mut serde_json::Deserializer::from_str(body);
+    serde_path_to_error::deserialize(de).map_err(|e| DeserializeError {
+        path: e.path().to_string(),
+        snippet: snippet(body),
+        source: Arc::new(e.into_inner()),
+    })
+}
+
+fn snippet(body: &str) -> String {
+    if body.len() <= SNIPPET_LEN {
+        return body.to_string();
+    }
+    let mut end = SNIPPET_LEN;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}...", &body[..end])
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at `{}` in {}", self.source, self.path, self.snippet)
+    }
+}
+
+impl Error for DeserializeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// The API reports private war logs as a plain `accessDenied` with an explanatory message,
+/// e.g. "Access denied, clan war log is private."
+fn mentions_private_war_log(error: &ClientError) -> bool {
+    error.message().as_deref().is_some_and(|message| {
+        let message = message.to_lowercase();
+        message.contains("war log") && message.contains("private")
+    })
+}
+
+impl From<TransportError> for ApiError {
+    fn from(e: TransportError) -> Self {
+        match e.kind() {
+            TransportErrorKind::Timeout => ApiError::Timeout(e),
+            _ => ApiError::Network(e),
+        }
+    }
+}
+
+impl From<TagError> for ApiError {
+    fn from(e: TagError) -> Self {
+        ApiError::InvalidTag(e)
+    }
+}
+
+/// Lets endpoints taking `impl TryInto<Tag>` accept an already parsed `Tag`.
+impl From<Infallible> for ApiError {
+    fn from(e: Infallible) -> Self {
+        match e {}
+    }
+}
+
+impl From<DeserializeError> for ApiError {
+    fn from(e: DeserializeError) -> Self {
+        ApiError::Deserialize(e)
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::NotFound(_) => write!(f, "not found")?,
+            ApiError::AccessDenied(_) => write!(f, "access denied")?,
+            ApiError::InvalidIp(_) => write!(f, "API key is not valid for this IP")?,
+            ApiError::PrivateWarLog(_) => write!(f, "the clan's war log is private")?,
+            ApiError::Throttled { retry_after, .. } => {
+                write!(f, "throttled")?;
+                if let Some(retry_after) = retry_after {
+                    write!(f, ", retry after {}s", retry_after.as_secs())?;
+                }
+            }
+            ApiError::Maintenance => write!(f, "the API is in maintenance")?,
+            ApiError::BadRequest(_) => write!(f, "bad request")?,
+            ApiError::Timeout(e) => write!(f, "request timed out: {}", e)?,
+            ApiError::Network(e) => write!(f, "request failed: {}", e)?,
+            ApiError::Deserialize(e) => write!(f, "unexpected response body: {}", e)?,
+            ApiError::Unknown(status, _) => write!(f, "API error {}", status)?,
+            ApiError::InvalidTag(e) => write!(f, "invalid tag: {}", e)?,
+            ApiError::NoKeys => write!(f, "no API key is registered for the client's public IPs")?,
+            ApiError::Login { status, message } => {
+                write!(f, "developer portal request failed with {}", status)?;
+                if let Some(message) = message {
+                    write!(f, ": {}", message)?;
+                }
+            }
+        }
+        if let Some(error) = self.client_error() {
+            write!(f, " ({}", error.reason())?;
+            if let Some(message) = error.message() {
+                write!(f, ": {}", message)?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ApiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ApiError::Timeout(e) | ApiError::Network(e) => Some(e),
+            ApiError::Deserialize(e) => Some(e),
+            ApiError::InvalidTag(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -1,5 +1,28 @@
-mod models;
+pub mod models;
+#[cfg(feature = "client")]
 pub mod api;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "client")]
+pub mod cache;
+#[cfg(feature = "client")]
+mod error;
+#[cfg(feature = "client")]
+pub mod http;
+#[cfg(feature = "extras")]
+pub mod linking;
+#[cfg(feature = "client")]
+mod maintenance;
+#[cfg(feature = "client")]
+pub mod middleware;
+#[cfg(feature = "client")]
+pub mod query;
+#[cfg(feature = "client")]
+pub mod rate_limit;
+#[cfg(feature = "client")]
+pub mod retry;
 mod test;
+#[cfg(feature = "dev")]
 mod dev;
+#[cfg(feature = "dev")]
 mod dev_models;
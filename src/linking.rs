@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::Mutex;
+
+use futures::future::{self, BoxFuture};
+
+use crate::api::{ApiError, Client};
+use crate::models::tag::Tag;
+
+/// Storage for verified tag -> external user links (e.g. a Discord user id),
+/// usually backed by a database.
+///
+/// Tags handed to the store are always normalized, as in `Tag::as_str`.
+pub trait LinkStore: Send + Sync {
+    fn linked_user<'a>(&'a self, tag: &'a str) -> BoxFuture<'a, Option<String>>;
+
+    /// Links `tag` to `user_id` unless the tag is already linked, in which case
+    /// the existing user is returned and nothing is changed.
+    fn link<'a>(&'a self, tag: &'a str, user_id: &'a str) -> BoxFuture<'a, Option<String>>;
+
+    fn unlink<'a>(&'a self, tag: &'a str) -> BoxFuture<'a, Option<String>>;
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryLinkStore {
+    links: Mutex<HashMap<String, String>>,
+}
+
+impl MemoryLinkStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl LinkStore for MemoryLinkStore {
+    fn linked_user<'a>(&'a self, tag: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(future::ready(self.links.lock().unwrap().get(tag).cloned()))
+    }
+
+    fn link<'a>(&'a self, tag: &'a str, user_id: &'a str) -> BoxFuture<'a, Option<String>> {
+        let mut links = self.links.lock().unwrap();
+        let existing = match links.get(tag) {
+            Some(existing) => Some(existing.clone()),
+            None => {
+                links.insert(tag.to_string(), user_id.to_string());
+                None
+            }
+        };
+        Box::pin(future::ready(existing))
+    }
+
+    fn unlink<'a>(&'a self, tag: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(future::ready(self.links.lock().unwrap().remove(tag)))
+    }
+}
+
+#[derive(Debug)]
+pub enum LinkError {
+    /// The tag is already linked to the contained user.
+    AlreadyLinked(String),
+    /// The API token did not verify the account.
+    InvalidToken,
+    Api(ApiError),
+}
+
+impl From<ApiError> for LinkError {
+    fn from(e: ApiError) -> Self {
+        LinkError::Api(e)
+    }
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkError::AlreadyLinked(user) => write!(f, "tag is already linked to {}", user),
+            LinkError::InvalidToken => write!(f, "the API token does not verify the account"),
+            LinkError::Api(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for LinkError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LinkError::Api(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AccountLinker<S: LinkStore> {
+    store: S,
+}
+
+impl<S: LinkStore> AccountLinker<S> {
+    pub fn new(store: S) -> Self {
+        Self { store }
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    pub async fn linked_user(&self, tag: &Tag) -> Option<String> {
+        self.store.linked_user(tag.as_str()).await
+    }
+
+    /// Verifies `token` for `tag` and links the account to `user_id`.
+    ///
+    /// Tags that are already linked are refused before the token is sent to the API.
+    pub async fn link<T>(
+        &self,
+        client: &Client,
+        tag: T,
+        token: String,
+        user_id: String,
+    ) -> Result<(), LinkError>
+    where
+        T: TryInto<Tag>,
+        ApiError: From<T::Error>,
+    {
+        let tag: Tag = tag.try_into().map_err(ApiError::from)?;
+        if let Some(existing) = self.store.linked_user(tag.as_str()).await {
+            return Err(LinkError::AlreadyLinked(existing));
+        }
+
+        let verified = client.get_verified_player::<&Tag>(&tag, token).await?;
+        if !verified.is_verified() {
+            return Err(LinkError::InvalidToken);
+        }
+
+        match self.store.link(tag.as_str(), &user_id).await {
+            Some(existing) => Err(LinkError::AlreadyLinked(existing)),
+            None => Ok(()),
+        }
+    }
+
+    pub async fn unlink(&self, tag: &Tag) -> Option<String> {
+        self.store.unlink(tag.as_str()).await
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use http::StatusCode;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+use crate::http::Response;
+use crate::models::client_error::ClientError;
+
+#[derive(Debug, Clone, Copy)]
+enum Circuit {
+    Closed,
+    Open { probe_at: Instant },
+    /// A single request was let through to check whether maintenance is over.
+    Probing { since: Instant },
+}
+
+/// Opens during game maintenance so requests fail fast instead of hammering the API.
+/// Once every `probe_interval` one request is let through, either a caller's or the
+/// client's background probe; the circuit closes as soon as a response is no longer
+/// a maintenance error.
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    circuit: Mutex<Circuit>,
+    probe_interval: Duration,
+    state: watch::Sender<bool>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(probe_interval: Duration) -> Self {
+        Self {
+            circuit: Mutex::new(Circuit::Closed),
+            probe_interval,
+            state: watch::channel(false).0,
+        }
+    }
+
+    /// Whether a request may be sent now.
+    pub(crate) fn allow(&self) -> bool {
+        let now = Instant::now();
+        let mut circuit = self.circuit.lock().unwrap();
+        match *circuit {
+            Circuit::Closed => true,
+            Circuit::Open { probe_at } if now >= probe_at => {
+                *circuit = Circuit::Probing { since: now };
+                true
+            }
+            // a probe that never reported back (e.g. it was cancelled) is replaced
+            Circuit::Probing { since } if now >= since + self.probe_interval => {
+                *circuit = Circuit::Probing { since: now };
+                true
+            }
+            Circuit::Open { .. } | Circuit::Probing { .. } => false,
+        }
+    }
+
+    /// Returns whether this response opened the circuit, i.e. maintenance just started.
+    pub(crate) fn record(&self, in_maintenance: bool) -> bool {
+        let mut circuit = self.circuit.lock().unwrap();
+        if in_maintenance {
+            *circuit = Circuit::Open {
+                probe_at: Instant::now() + self.probe_interval,
+            };
+        } else {
+            *circuit = Circuit::Closed;
+        }
+        self.state.send_if_modified(|state| std::mem::replace(state, in_maintenance) != in_maintenance)
+            && in_maintenance
+    }
+
+    pub(crate) fn probe_interval(&self) -> Duration {
+        self.probe_interval
+    }
+
+    pub(crate) fn in_maintenance(&self) -> bool {
+        *self.state.borrow()
+    }
+
+    pub(crate) fn subscribe(&self) -> watch::Receiver<bool> {
+        self.state.subscribe()
+    }
+}
+
+pub(crate) fn is_maintenance(res: &Response) -> bool {
+    res.status() == StatusCode::SERVICE_UNAVAILABLE
+        && serde_json::from_str::<ClientError>(res.body())
+            .is_ok_and(|body| body.reason() == "inMaintenance")
+}
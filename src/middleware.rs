@@ -0,0 +1,24 @@
+use std::fmt::Debug;
+
+use crate::api::ApiError;
+use crate::http::{Request, Response};
+
+/// Hooks around every HTTP request a `Client` sends, including each retry attempt.
+///
+/// Before hooks run in the order the middleware was added, after and error hooks in reverse.
+/// Responses served from the cache, or shared with an identical request already in
+/// flight, do not pass through middleware.
+pub trait Middleware: Debug + Send + Sync {
+    /// Called before the request is sent. The API key is attached afterwards,
+    /// so it is never visible here. Returning a response skips the remaining
+    /// before hooks and the network; the response still passes through the after hooks.
+    fn before_request(&self, _request: &mut Request) -> Option<Response> {
+        None
+    }
+
+    fn after_response(&self, _request: &Request, _response: &mut Response) {}
+
+    /// Called instead of `after_response` when no response was received, e.g. on a
+    /// timeout, a connection error or when the client has no API key.
+    fn on_error(&self, _request: &Request, _error: &ApiError) {}
+}
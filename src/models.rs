@@ -1,7 +1,10 @@
 pub mod badge_urls;
 pub mod clan;
+pub mod client_error;
 pub mod current_war;
 pub mod gold_pass;
 pub mod paging;
 pub mod player;
+pub mod ranking;
+pub mod tag;
 pub mod war_log;
@@ -1,6 +1,10 @@
+use std::collections::HashMap;
+
 use serde::{ Serialize, Deserialize };
+use serde_json::Value;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct BadgeUrls {
     #[serde(rename = "small")]
     small: String,
@@ -10,6 +14,9 @@ pub struct BadgeUrls {
 
     #[serde(rename = "medium")]
     medium: String,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
 impl BadgeUrls {
@@ -22,4 +29,7 @@ impl BadgeUrls {
     pub fn medium(&self) -> &str {
         &self.medium
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
@@ -1,8 +1,12 @@
 #[allow(dead_code)]
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use crate::models::badge_urls::BadgeUrls;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct Clan {
     #[serde(rename = "tag")]
     tag: String,
@@ -52,23 +56,27 @@ pub struct Clan {
     #[serde(rename = "members")]
     members: i32,
 
-    #[serde(rename = "memberList")]
+    #[serde(rename = "memberList", default)]
     member_list: Vec<ClanMember>,
 
-    #[serde(rename = "labels")]
+    #[serde(rename = "labels", default)]
     labels: Vec<Label>,
 
     #[serde(rename = "chatLanguage")]
-    chat_language: ChatLanguage,
+    chat_language: Option<ChatLanguage>,
 
     #[serde(rename = "requiredVersusTrophies")]
     required_versus_trophies: i32,
 
     #[serde(rename = "requiredTownhallLevel")]
     required_townhall_level: i8,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct ChatLanguage {
     #[serde(rename = "id")]
     id: i32,
@@ -78,9 +86,13 @@ pub struct ChatLanguage {
 
     #[serde(rename = "languageCode")]
     language_code: String,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct Label {
     #[serde(rename = "id")]
     id: i32,
@@ -90,18 +102,26 @@ pub struct Label {
 
     #[serde(rename = "iconUrls")]
     icon_urls: LabelIconUrls,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct LabelIconUrls {
     #[serde(rename = "small")]
     small: String,
 
     #[serde(rename = "medium")]
     medium: String,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct Location {
     #[serde(rename = "id")]
     id: i32,
@@ -111,9 +131,13 @@ pub struct Location {
 
     #[serde(rename = "isCountry")]
     is_country: bool,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct ClanMember {
     #[serde(rename = "tag")]
     tag: String,
@@ -128,7 +152,7 @@ pub struct ClanMember {
     exp_level: i32,
 
     #[serde(rename = "league")]
-    league: League,
+    league: Option<League>,
 
     #[serde(rename = "trophies")]
     trophies: i32,
@@ -147,9 +171,13 @@ pub struct ClanMember {
 
     #[serde(rename = "donationsReceived")]
     donations_received: i32,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct League {
     #[serde(rename = "id")]
     id: i32,
@@ -159,9 +187,13 @@ pub struct League {
 
     #[serde(rename = "iconUrls")]
     icon_urls: LeagueIconUrls,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct LeagueIconUrls {
     #[serde(rename = "small")]
     small: String,
@@ -171,30 +203,34 @@ pub struct LeagueIconUrls {
 
     #[serde(rename = "medium")]
     medium: Option<String>,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct WarLeague {
     #[serde(rename = "id")]
     id: i32,
 
     #[serde(rename = "name")]
     name: String,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
 pub enum Role {
-    #[serde(rename = "admin")]
     Admin,
-
-    #[serde(rename = "coLeader")]
     CoLeader,
-
-    #[serde(rename = "leader")]
     Leader,
-
-    #[serde(rename = "member")]
+    #[default]
     Member,
+    /// A role added to the game after this version of the library.
+    Unknown(String),
 }
 
 impl Clan {
@@ -252,7 +288,7 @@ impl Clan {
     pub fn labels(&self) -> &Vec<Label> {
         &self.labels
     }
-    pub fn chat_language(&self) -> &ChatLanguage {
+    pub fn chat_language(&self) -> &Option<ChatLanguage> {
         &self.chat_language
     }
     pub fn required_versus_trophies(&self) -> i32 {
@@ -261,15 +297,45 @@ impl Clan {
     pub fn required_townhall_level(&self) -> i8 {
         self.required_townhall_level
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
 
 impl Role {
-    pub fn to_string(&self) -> &str {
+    /// The role as the API spells it, e.g. `coLeader`.
+    pub fn as_str(&self) -> &str {
         match self {
             Role::Admin => "admin",
             Role::CoLeader => "coLeader",
             Role::Leader => "leader",
             Role::Member => "member",
+            Role::Unknown(role) => role,
+        }
+    }
+
+    pub fn to_string(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<String> for Role {
+    fn from(role: String) -> Self {
+        match role.as_str() {
+            "admin" => Role::Admin,
+            "coLeader" => Role::CoLeader,
+            "leader" => Role::Leader,
+            "member" => Role::Member,
+            _ => Role::Unknown(role),
+        }
+    }
+}
+
+impl From<Role> for String {
+    fn from(role: Role) -> Self {
+        match role {
+            Role::Unknown(role) => role,
+            role => role.as_str().to_string(),
         }
     }
 }
@@ -281,6 +347,9 @@ impl WarLeague {
     pub fn name(&self) -> &str {
         &self.name
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
 
 impl LeagueIconUrls {
@@ -293,6 +362,9 @@ impl LeagueIconUrls {
     pub fn medium(&self) -> &Option<String> {
         &self.medium
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
 
 impl League {
@@ -305,6 +377,9 @@ impl League {
     pub fn icon_urls(&self) -> &LeagueIconUrls {
         &self.icon_urls
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
 
 impl ClanMember {
@@ -320,7 +395,7 @@ impl ClanMember {
     pub fn exp_level(&self) -> i32 {
         self.exp_level
     }
-    pub fn league(&self) -> &League {
+    pub fn league(&self) -> &Option<League> {
         &self.league
     }
     pub fn trophies(&self) -> i32 {
@@ -341,6 +416,9 @@ impl ClanMember {
     pub fn donations_received(&self) -> i32 {
         self.donations_received
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
 
 impl Location {
@@ -353,6 +431,9 @@ impl Location {
     pub fn is_country(&self) -> bool {
         self.is_country
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
 
 impl LabelIconUrls {
@@ -362,6 +443,9 @@ impl LabelIconUrls {
     pub fn medium(&self) -> &str {
         &self.medium
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
 
 impl Label {
@@ -374,6 +458,9 @@ impl Label {
     pub fn icon_urls(&self) -> &LabelIconUrls {
         &self.icon_urls
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
 
 impl ChatLanguage {
@@ -386,4 +473,7 @@ impl ChatLanguage {
     pub fn language_code(&self) -> &str {
         &self.language_code
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// The body the API sends along with an error status.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientError {
+    #[serde(rename = "reason")]
+    reason: String,
+
+    #[serde(rename = "message")]
+    message: Option<String>,
+
+    #[serde(rename = "type")]
+    error_type: Option<String>,
+
+    #[serde(rename = "detail")]
+    detail: Option<serde_json::Value>,
+}
+
+impl ClientError {
+    /// e.g. `notFound`, `accessDenied.invalidIp`, `inMaintenance`
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+    pub fn message(&self) -> &Option<String> {
+        &self.message
+    }
+    pub fn error_type(&self) -> &Option<String> {
+        &self.error_type
+    }
+    pub fn detail(&self) -> &Option<serde_json::Value> {
+        &self.detail
+    }
+}
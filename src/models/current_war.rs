@@ -1,10 +1,15 @@
+use std::collections::HashMap;
+use std::fmt;
+
 use serde::{ Serialize, Deserialize };
+use serde_json::Value;
 use crate::models::badge_urls::BadgeUrls;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct War {
     #[serde(rename = "state")]
-    state: String,
+    state: WarState,
 
     #[serde(rename = "teamSize")]
     team_size: Option<i32>,
@@ -26,9 +31,25 @@ pub struct War {
 
     #[serde(rename = "opponent")]
     opponent: Option<WarClan>,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum WarState {
+    #[default]
+    NotInWar,
+    Preparation,
+    InWar,
+    WarEnded,
+    /// A state added to the game after this version of the library.
+    Unknown(String),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct WarClan {
     #[serde(rename = "tag")]
     tag: Option<String>,
@@ -53,10 +74,14 @@ pub struct WarClan {
 
     #[serde(rename = "members")]
     members: Option<Vec<Member>>,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct Member {
     #[serde(rename = "tag")]
     tag: String,
@@ -77,10 +102,14 @@ pub struct Member {
     opponent_attacks: i32,
 
     #[serde(rename = "bestOpponentAttack")]
-    best_opponent_attack: Attack,
+    best_opponent_attack: Option<Attack>,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct Attack {
     #[serde(rename = "attackerTag")]
     attacker_tag: String,
@@ -99,12 +128,21 @@ pub struct Attack {
 
     #[serde(rename = "duration")]
     duration: i32,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
 impl War {
-    pub fn state(&self) -> &str {
+    pub fn state(&self) -> &WarState {
         &self.state
     }
+    pub fn is_active(&self) -> bool {
+        self.state.is_active()
+    }
+    pub fn has_ended(&self) -> bool {
+        self.state == WarState::WarEnded
+    }
     pub fn team_size(&self) -> Option<i32> {
         self.team_size
     }
@@ -126,6 +164,53 @@ impl War {
     pub fn opponent(&self) -> &Option<WarClan> {
         &self.opponent
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
+}
+
+impl WarState {
+    pub fn as_str(&self) -> &str {
+        match self {
+            WarState::NotInWar => "notInWar",
+            WarState::Preparation => "preparation",
+            WarState::InWar => "inWar",
+            WarState::WarEnded => "warEnded",
+            WarState::Unknown(state) => state,
+        }
+    }
+
+    /// In preparation or battle day.
+    pub fn is_active(&self) -> bool {
+        matches!(self, WarState::Preparation | WarState::InWar)
+    }
+}
+
+impl From<String> for WarState {
+    fn from(state: String) -> Self {
+        match state.as_str() {
+            "notInWar" => WarState::NotInWar,
+            "preparation" => WarState::Preparation,
+            "inWar" => WarState::InWar,
+            "warEnded" => WarState::WarEnded,
+            _ => WarState::Unknown(state),
+        }
+    }
+}
+
+impl From<WarState> for String {
+    fn from(state: WarState) -> Self {
+        match state {
+            WarState::Unknown(state) => state,
+            state => state.as_str().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for WarState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 impl WarClan {
@@ -153,6 +238,9 @@ impl WarClan {
     pub fn members(&self) -> &Option<Vec<Member>> {
         &self.members
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
 
 impl Member {
@@ -174,9 +262,12 @@ impl Member {
     pub fn opponent_attacks(&self) -> i32 {
         self.opponent_attacks
     }
-    pub fn best_opponent_attack(&self) -> &Attack {
+    pub fn best_opponent_attack(&self) -> &Option<Attack> {
         &self.best_opponent_attack
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
 
 impl Attack {
@@ -198,4 +289,7 @@ impl Attack {
     pub fn duration(&self) -> i32 {
         self.duration
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
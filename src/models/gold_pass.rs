@@ -1,14 +1,21 @@
+use std::collections::HashMap;
+
 use serde::{ Serialize, Deserialize };
+use serde_json::Value;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct GoldPass {
     #[serde(rename = "stratTime")]
     start_time: String, 
     #[serde(rename = "endTime")]
     end_time: String,
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
 impl GoldPass {
     pub fn start_time(&self) -> &str{ &self.start_time }
     pub fn end_time(&self) -> &str { &self.end_time }
+    pub fn extra(&self) -> &HashMap<String, Value> { &self.extra }
 }
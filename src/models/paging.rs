@@ -1,24 +1,58 @@
 use serde::{ Serialize, Deserialize };
 
+/// One page of a paged endpoint such as a clan's members or war log.
 #[derive(Debug, Serialize, Deserialize)]
+pub struct Page<T> {
+    #[serde(rename = "items")]
+    items: Vec<T>,
+
+    #[serde(rename = "paging", default)]
+    paging: Paging,
+
+    /// The request this page answers, without its cursor, so it can be replayed.
+    #[serde(skip)]
+    query: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 struct Paging {
-    #[serde(rename = "cursors")]
+    #[serde(rename = "cursors", default)]
     cursors: Cursors,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-enum Cursors {
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Cursors {
     #[serde(rename = "before")]
-    Before(String),
+    before: Option<String>,
+
     #[serde(rename = "after")]
-    After(String),
+    after: Option<String>,
 }
 
-impl Paging {
-    pub fn before(&self) -> &str {
-        match self.cursors {
-            Cursors::Before(ref s) => s,
-            _ => panic!("Paging::before: not a before cursor"),
-        }
+impl<T> Page<T> {
+    pub fn items(&self) -> &Vec<T> {
+        &self.items
+    }
+    pub fn into_items(self) -> Vec<T> {
+        self.items
+    }
+    /// Cursor of the following page, `None` on the last one.
+    pub fn next_cursor(&self) -> Option<&str> {
+        self.paging.cursors.after.as_deref()
+    }
+    /// Cursor of the preceding page, `None` on the first one.
+    pub fn prev_cursor(&self) -> Option<&str> {
+        self.paging.cursors.before.as_deref()
+    }
+
+    #[cfg_attr(not(feature = "client"), allow(dead_code))]
+    pub(crate) fn query(&self) -> Option<&str> {
+        self.query.as_deref()
     }
-}
\ No newline at end of file
+
+    #[cfg_attr(not(feature = "client"), allow(dead_code))]
+    pub(crate) fn with_query(mut self, query: String) -> Self {
+        self.query = Some(query);
+        self
+    }
+}
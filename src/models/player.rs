@@ -1,7 +1,11 @@
 #[allow(dead_code)]
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use crate::models::badge_urls::BadgeUrls;
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct Player {
     #[serde(rename = "tag")]
     tag: String,
@@ -54,25 +58,29 @@ pub struct Player {
     #[serde(rename = "clan")]
     clan: Option<PlayerClan>,
 
-    #[serde(rename = "achievements")]
+    #[serde(rename = "achievements", default)]
     achievements: Vec<Achievement>,
 
     #[serde(rename = "versusBattleWinCount")]
     versus_battle_win_count: i32,
 
-    #[serde(rename = "labels")]
+    #[serde(rename = "labels", default)]
     labels: Vec<Option<Label>>,
 
-    #[serde(rename = "troops")]
+    #[serde(rename = "troops", default)]
     troops: Vec<Troop>,
 
-    #[serde(rename = "heroes")]
+    #[serde(rename = "heroes", default)]
     heroes: Vec<Option<Hero>>,
 
-    #[serde(rename = "spells")]
+    #[serde(rename = "spells", default)]
     spells: Vec<Spell>,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct Label {
     #[serde(rename = "name")]
     name: String,
@@ -82,9 +90,13 @@ pub struct Label {
 
     #[serde(rename = "iconUrls")]
     icon_urls: Vec<String>,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct Hero {
     #[serde(rename = "name")]
     name: String,
@@ -97,8 +109,12 @@ pub struct Hero {
 
     #[serde(rename = "village")]
     village: Village,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct Achievement {
     #[serde(rename = "name")]
     name: String,
@@ -120,9 +136,13 @@ pub struct Achievement {
 
     #[serde(rename = "village")]
     village: Village,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct PlayerClan {
     #[serde(rename = "tag")]
     tag: String,
@@ -135,9 +155,13 @@ pub struct PlayerClan {
 
     #[serde(rename = "badgeUrls")]
     badge_urls: BadgeUrls,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct Spell {
     #[serde(rename = "name")]
     name: String,
@@ -150,9 +174,13 @@ pub struct Spell {
 
     #[serde(rename = "village")]
     village: Village,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct Troop {
     #[serde(rename = "name")]
     name: String,
@@ -165,15 +193,48 @@ pub struct Troop {
 
     #[serde(rename = "village")]
     village: Village,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
 pub enum Village {
-    #[serde(rename = "builderBase")]
     BuilderBase,
-
-    #[serde(rename = "home")]
+    #[default]
     Home,
+    /// A village added to the game after this version of the library.
+    Unknown(String),
+}
+
+impl Village {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Village::BuilderBase => "builderBase",
+            Village::Home => "home",
+            Village::Unknown(village) => village,
+        }
+    }
+}
+
+impl From<String> for Village {
+    fn from(village: String) -> Self {
+        match village.as_str() {
+            "builderBase" => Village::BuilderBase,
+            "home" => Village::Home,
+            _ => Village::Unknown(village),
+        }
+    }
+}
+
+impl From<Village> for String {
+    fn from(village: Village) -> Self {
+        match village {
+            Village::Unknown(village) => village,
+            village => village.as_str().to_string(),
+        }
+    }
 }
 
 impl Player {
@@ -246,6 +307,42 @@ impl Player {
     pub fn spells(&self) -> &Vec<Spell> {
         &self.spells
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
+}
+
+impl Label {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+    pub fn icon_urls(&self) -> &Vec<String> {
+        &self.icon_urls
+    }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
+}
+
+impl Hero {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn level(&self) -> i32 {
+        self.level
+    }
+    pub fn max_level(&self) -> i32 {
+        self.max_level
+    }
+    pub fn village(&self) -> &Village {
+        &self.village
+    }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
 
 impl PlayerClan {
@@ -261,6 +358,9 @@ impl PlayerClan {
     pub fn badge_urls(&self) -> &BadgeUrls {
         &self.badge_urls
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
 
 impl Spell {
@@ -276,6 +376,9 @@ impl Spell {
     pub fn village(&self) -> &Village {
         &self.village
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
 
 impl Achievement {
@@ -300,6 +403,9 @@ impl Achievement {
     pub fn village(&self) -> &Village {
         &self.village
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
 
 impl Troop {
@@ -315,6 +421,9 @@ impl Troop {
     pub fn village(&self) -> &Village {
         &self.village
     }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -324,7 +433,16 @@ pub struct PlayerToken {
     #[serde(rename = "token")]
     token: String,
     #[serde(rename = "status")]
-    status: String,
+    status: VerificationStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VerificationStatus {
+    #[serde(rename = "ok")]
+    Ok,
+
+    #[serde(rename = "invalid")]
+    Invalid,
 }
 
 impl PlayerToken{
@@ -334,7 +452,10 @@ impl PlayerToken{
     pub fn token(&self) -> &str {
         &self.token
     }
-    pub fn status(&self) -> &str {
-        &self.status
+    pub fn status(&self) -> VerificationStatus {
+        self.status
+    }
+    pub fn is_verified(&self) -> bool {
+        self.status == VerificationStatus::Ok
     }
 }
\ No newline at end of file
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::badge_urls::BadgeUrls;
+use crate::models::clan::{League, Location};
+use crate::models::player::PlayerClan;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
+pub struct ClanRanking {
+    #[serde(rename = "tag")]
+    tag: String,
+
+    #[serde(rename = "name")]
+    name: String,
+
+    #[serde(rename = "location")]
+    location: Option<Location>,
+
+    #[serde(rename = "badgeUrls")]
+    badge_urls: BadgeUrls,
+
+    #[serde(rename = "clanLevel")]
+    clan_level: i8,
+
+    #[serde(rename = "members")]
+    members: i32,
+
+    #[serde(rename = "clanPoints")]
+    clan_points: i32,
+
+    #[serde(rename = "rank")]
+    rank: i32,
+
+    #[serde(rename = "previousRank")]
+    previous_rank: i32,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
+pub struct PlayerRanking {
+    #[serde(rename = "tag")]
+    tag: String,
+
+    #[serde(rename = "name")]
+    name: String,
+
+    #[serde(rename = "expLevel")]
+    exp_level: i32,
+
+    #[serde(rename = "trophies")]
+    trophies: i32,
+
+    #[serde(rename = "attackWins")]
+    attack_wins: i32,
+
+    #[serde(rename = "defenseWins")]
+    defense_wins: i32,
+
+    #[serde(rename = "rank")]
+    rank: i32,
+
+    #[serde(rename = "previousRank")]
+    previous_rank: i32,
+
+    #[serde(rename = "clan")]
+    clan: Option<PlayerClan>,
+
+    #[serde(rename = "league")]
+    league: Option<League>,
+
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
+}
+
+impl ClanRanking {
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn location(&self) -> &Option<Location> {
+        &self.location
+    }
+    pub fn badge_urls(&self) -> &BadgeUrls {
+        &self.badge_urls
+    }
+    pub fn clan_level(&self) -> i8 {
+        self.clan_level
+    }
+    pub fn members(&self) -> i32 {
+        self.members
+    }
+    pub fn clan_points(&self) -> i32 {
+        self.clan_points
+    }
+    pub fn rank(&self) -> i32 {
+        self.rank
+    }
+    pub fn previous_rank(&self) -> i32 {
+        self.previous_rank
+    }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
+}
+
+impl PlayerRanking {
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn exp_level(&self) -> i32 {
+        self.exp_level
+    }
+    pub fn trophies(&self) -> i32 {
+        self.trophies
+    }
+    pub fn attack_wins(&self) -> i32 {
+        self.attack_wins
+    }
+    pub fn defense_wins(&self) -> i32 {
+        self.defense_wins
+    }
+    pub fn rank(&self) -> i32 {
+        self.rank
+    }
+    pub fn previous_rank(&self) -> i32 {
+        self.previous_rank
+    }
+    pub fn clan(&self) -> &Option<PlayerClan> {
+        &self.clan
+    }
+    pub fn league(&self) -> &Option<League> {
+        &self.league
+    }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
+}
@@ -0,0 +1,174 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Characters a player or clan tag can be made of, after the leading `#`.
+pub(crate) const TAG_ALPHABET: &str = "0289PYLQGRJCUV";
+
+/// Longest tag, after the `#`, whose id is guaranteed to fit in a `u64`.
+const MAX_TAG_LEN: usize = 16;
+
+/// A validated player or clan tag, stored normalized as `#` followed by uppercase
+/// characters of the tag alphabet.
+///
+/// Parsing is forgiving about what users type: case, a missing `#`, surrounding
+/// whitespace and the letter `O` in place of zero are all accepted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Tag(String);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagError {
+    Empty,
+    /// A character outside of the tag alphabet.
+    InvalidChar(char),
+    TooLong,
+    /// Tags encode a number, so they never start with `0`.
+    LeadingZero,
+}
+
+impl Tag {
+    /// e.g. `#2PP`
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The tag as it goes into a request path, e.g. `%232PP`.
+    pub fn url_encoded(&self) -> String {
+        format!("%23{}", &self.0[1..])
+    }
+
+    /// The account id the tag encodes in base 14. `Tag::from_id` turns it back into the same tag.
+    pub fn to_id(&self) -> u64 {
+        self.0[1..].chars().fold(0, |id, c| {
+            // parsing guarantees the alphabet and a length that cannot overflow
+            id * 14 + TAG_ALPHABET.find(c).unwrap() as u64
+        })
+    }
+
+    /// `None` for `0` and for ids too large to be a tag.
+    pub fn from_id(mut id: u64) -> Option<Tag> {
+        if id == 0 {
+            return None;
+        }
+        let alphabet = TAG_ALPHABET.as_bytes();
+        let mut digits = Vec::new();
+        while id > 0 {
+            digits.push(alphabet[(id % 14) as usize] as char);
+            id /= 14;
+        }
+        if digits.len() > MAX_TAG_LEN {
+            return None;
+        }
+        Some(Tag(std::iter::once('#').chain(digits.into_iter().rev()).collect()))
+    }
+
+    /// The id split the way the game stores it: `high` is its lowest 8 bits, `low` the rest.
+    /// `None` if `low` does not fit in a `u32`.
+    pub fn to_high_low(&self) -> Option<(u32, u32)> {
+        let id = self.to_id();
+        Some(((id & 0xFF) as u32, u32::try_from(id >> 8).ok()?))
+    }
+
+    /// `None` if `high` is larger than 8 bits or both parts are `0`.
+    pub fn from_high_low(high: u32, low: u32) -> Option<Tag> {
+        if high > 0xFF {
+            return None;
+        }
+        Tag::from_id(u64::from(low) << 8 | u64::from(high))
+    }
+}
+
+impl FromStr for Tag {
+    type Err = TagError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_prefix('#').unwrap_or(s);
+        if s.is_empty() {
+            return Err(TagError::Empty);
+        }
+        if s.chars().count() > MAX_TAG_LEN {
+            return Err(TagError::TooLong);
+        }
+        let mut tag = String::with_capacity(s.len() + 1);
+        tag.push('#');
+        for c in s.chars() {
+            let c = match c.to_ascii_uppercase() {
+                'O' => '0',
+                c => c,
+            };
+            if !TAG_ALPHABET.contains(c) {
+                return Err(TagError::InvalidChar(c));
+            }
+            tag.push(c);
+        }
+        if tag[1..].starts_with('0') {
+            return Err(TagError::LeadingZero);
+        }
+        Ok(Tag(tag))
+    }
+}
+
+impl TryFrom<&str> for Tag {
+    type Error = TagError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl TryFrom<String> for Tag {
+    type Error = TagError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl TryFrom<&String> for Tag {
+    type Error = TagError;
+
+    fn try_from(s: &String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<&Tag> for Tag {
+    fn from(tag: &Tag) -> Self {
+        tag.clone()
+    }
+}
+
+impl From<Tag> for String {
+    fn from(tag: Tag) -> Self {
+        tag.0
+    }
+}
+
+impl AsRef<str> for Tag {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Display for TagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagError::Empty => write!(f, "tag is empty"),
+            TagError::InvalidChar(c) => write!(f, "tag contains `{}`, which is not one of {}", c, TAG_ALPHABET),
+            TagError::TooLong => write!(f, "tag is longer than {} characters", MAX_TAG_LEN),
+            TagError::LeadingZero => write!(f, "tag starts with 0"),
+        }
+    }
+}
+
+impl Error for TagError {}
@@ -1,12 +1,17 @@
 use serde::Deserialize;
 use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
 
 use crate::models::badge_urls::BadgeUrls;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct WarLog {
+    /// `None` for Clan War League wars.
     #[serde(rename = "result" )]
-    result: String,
+    result: Option<WarResult>,
     #[serde(rename = "endTime" )]
     end_time: String,
     #[serde(rename = "teamSize" )]
@@ -17,9 +22,22 @@ pub struct WarLog {
     clan: Clan,
     #[serde(rename = "opponent")]
     opponent: Opponent,
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum WarResult {
+    Win,
+    Lose,
+    Tie,
+    /// A result added to the game after this version of the library.
+    Unknown(String),
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 pub struct Clan {
     #[serde(rename = "tag")]
     tag: String,
@@ -37,9 +55,12 @@ pub struct Clan {
     destruction_percentage: f32,
     #[serde(rename = "expEarned")]
     exp_earned: i32,
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "lenient", serde(default))]
 #[serde(rename_all = "camelCase")]
 pub struct Opponent {
     #[serde(rename = "tag")]
@@ -54,4 +75,100 @@ pub struct Opponent {
     pub stars: i64,
     #[serde(rename = "destructionPercentage")]
     pub destruction_percentage: f32,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl WarLog {
+    pub fn result(&self) -> &Option<WarResult> {
+        &self.result
+    }
+    pub fn is_win(&self) -> bool {
+        self.result == Some(WarResult::Win)
+    }
+    pub fn end_time(&self) -> &str {
+        &self.end_time
+    }
+    pub fn team_size(&self) -> i32 {
+        self.team_size
+    }
+    pub fn attacks_per_member(&self) -> i8 {
+        self.attacks_per_member
+    }
+    pub fn clan(&self) -> &Clan {
+        &self.clan
+    }
+    pub fn opponent(&self) -> &Opponent {
+        &self.opponent
+    }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
+}
+
+impl WarResult {
+    pub fn as_str(&self) -> &str {
+        match self {
+            WarResult::Win => "win",
+            WarResult::Lose => "lose",
+            WarResult::Tie => "tie",
+            WarResult::Unknown(result) => result,
+        }
+    }
+}
+
+impl From<String> for WarResult {
+    fn from(result: String) -> Self {
+        match result.as_str() {
+            "win" => WarResult::Win,
+            "lose" => WarResult::Lose,
+            "tie" => WarResult::Tie,
+            _ => WarResult::Unknown(result),
+        }
+    }
+}
+
+impl From<WarResult> for String {
+    fn from(result: WarResult) -> Self {
+        match result {
+            WarResult::Unknown(result) => result,
+            result => result.as_str().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for WarResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Clan {
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn badge_urls(&self) -> &BadgeUrls {
+        &self.badge_urls
+    }
+    pub fn clan_level(&self) -> i8 {
+        self.clan_level
+    }
+    pub fn attacks(&self) -> i32 {
+        self.attacks
+    }
+    pub fn stars(&self) -> i32 {
+        self.stars
+    }
+    pub fn destruction_percentage(&self) -> f32 {
+        self.destruction_percentage
+    }
+    pub fn exp_earned(&self) -> i32 {
+        self.exp_earned
+    }
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
 }
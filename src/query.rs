@@ -0,0 +1,153 @@
+use url::form_urlencoded;
+
+/// Query parameters of a list endpoint: paging plus any endpoint specific filters.
+/// Values are URL-encoded when the query is appended to a request.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    params: Vec<(&'static str, String)>,
+}
+
+impl Query {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum number of items per page.
+    pub fn limit(self, limit: u32) -> Self {
+        self.set("limit", limit)
+    }
+
+    /// Fetch the page after this cursor. Replaces any `before` cursor.
+    pub fn after(self, cursor: impl Into<String>) -> Self {
+        self.unset("before").set("after", cursor.into())
+    }
+
+    /// Fetch the page before this cursor. Replaces any `after` cursor.
+    pub fn before(self, cursor: impl Into<String>) -> Self {
+        self.unset("after").set("before", cursor.into())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    /// Sets `key`, replacing its previous value.
+    pub(crate) fn set(mut self, key: &'static str, value: impl ToString) -> Self {
+        let value = value.to_string();
+        match self.params.iter_mut().find(|(k, _)| *k == key) {
+            Some(param) => param.1 = value,
+            None => self.params.push((key, value)),
+        }
+        self
+    }
+
+    pub(crate) fn unset(mut self, key: &str) -> Self {
+        self.params.retain(|(k, _)| *k != key);
+        self
+    }
+
+    /// The same query without its paging cursor.
+    pub(crate) fn without_cursor(self) -> Self {
+        self.unset("after").unset("before")
+    }
+
+    /// Appends the encoded query to `url`, which may already carry parameters.
+    pub(crate) fn append_to(&self, url: &str) -> String {
+        if self.is_empty() {
+            return url.to_string();
+        }
+        let separator = if url.contains('?') { '&' } else { '?' };
+        format!("{}{}{}", url, separator, self)
+    }
+}
+
+impl std::fmt::Display for Query {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut serializer = form_urlencoded::Serializer::new(String::new());
+        for (key, value) in &self.params {
+            serializer.append_pair(key, value);
+        }
+        f.write_str(&serializer.finish())
+    }
+}
+
+/// Filters for `Client::search_clans`. At least one filter is required by the API.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClanSearch {
+    query: Query,
+}
+
+impl ClanSearch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// At least three characters.
+    pub fn name(self, name: impl Into<String>) -> Self {
+        self.set("name", name.into())
+    }
+
+    /// e.g. `always`, `moreThanOncePerWeek`, `oncePerWeek`, `lessThanOncePerWeek`, `never`
+    pub fn war_frequency(self, frequency: impl Into<String>) -> Self {
+        self.set("warFrequency", frequency.into())
+    }
+
+    pub fn location_id(self, id: u32) -> Self {
+        self.set("locationId", id)
+    }
+
+    pub fn min_members(self, members: u32) -> Self {
+        self.set("minMembers", members)
+    }
+
+    pub fn max_members(self, members: u32) -> Self {
+        self.set("maxMembers", members)
+    }
+
+    pub fn min_clan_points(self, points: u32) -> Self {
+        self.set("minClanPoints", points)
+    }
+
+    pub fn min_clan_level(self, level: u32) -> Self {
+        self.set("minClanLevel", level)
+    }
+
+    /// Clans must carry every one of these labels.
+    pub fn label_ids(self, ids: &[u32]) -> Self {
+        let ids: Vec<String> = ids.iter().map(u32::to_string).collect();
+        self.set("labelIds", ids.join(","))
+    }
+
+    pub fn limit(self, limit: u32) -> Self {
+        Self { query: self.query.limit(limit) }
+    }
+
+    pub fn after(self, cursor: impl Into<String>) -> Self {
+        Self { query: self.query.after(cursor) }
+    }
+
+    pub fn before(self, cursor: impl Into<String>) -> Self {
+        Self { query: self.query.before(cursor) }
+    }
+
+    pub fn query(&self) -> &Query {
+        &self.query
+    }
+
+    fn set(self, key: &'static str, value: impl ToString) -> Self {
+        Self { query: self.query.set(key, value) }
+    }
+}
+
+impl From<ClanSearch> for Query {
+    fn from(search: ClanSearch) -> Self {
+        search.query
+    }
+}
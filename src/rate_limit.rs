@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tokio::sync::Notify;
+
+/// Allowed request rate for a single API key.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    requests: u32,
+    per: Duration,
+}
+
+impl RateLimit {
+    pub fn new(requests: u32, per: Duration) -> Self {
+        assert!(requests > 0, "RateLimit::new: requests must be greater than 0");
+        assert!(!per.is_zero(), "RateLimit::new: period must not be zero");
+        Self { requests, per }
+    }
+
+    pub fn per_second(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(1))
+    }
+
+    pub fn requests(&self) -> u32 {
+        self.requests
+    }
+    pub fn per(&self) -> Duration {
+        self.per
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(limit: RateLimit, now: Instant) -> Self {
+        let capacity = limit.requests as f64;
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_sec: capacity / limit.per.as_secs_f64(),
+            last_refill: now,
+        }
+    }
+
+    /// Takes a token, or returns how long until one becomes available.
+    pub(crate) fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        let wait = self.available_in(now);
+        if wait.is_zero() {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(wait)
+        }
+    }
+
+    /// How long until a token is available, without taking it.
+    pub(crate) fn available_in(&mut self, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_sec)
+        }
+    }
+}
+
+/// Scheduling class of a request. When keys are saturated, waiting requests of a
+/// higher priority always get the next free capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    /// Crawls and other bulk work.
+    Background,
+    #[default]
+    Normal,
+    /// User-facing requests, e.g. bot commands.
+    Interactive,
+}
+
+impl Priority {
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Debug, Default)]
+struct LimiterState {
+    buckets: HashMap<String, TokenBucket>,
+    waiting: [usize; 3],
+}
+
+/// Per-key token buckets shared by every request a `Client` sends.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    limit: RateLimit,
+    state: Mutex<LimiterState>,
+    /// Wakes requests blocked by a higher priority whenever a waiter leaves.
+    released: Notify,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            state: Mutex::new(LimiterState::default()),
+            released: Notify::new(),
+        }
+    }
+
+    /// Waits until one of the keys identified by `ids` has capacity and returns
+    /// its index. Keys are tried in order, and only once no request of a higher
+    /// priority is waiting.
+    pub(crate) async fn acquire(&self, ids: &[&str], priority: Priority) -> usize {
+        let _waiter = Waiter::register(self, priority);
+        loop {
+            // registered before checking, so a waiter leaving in between is not missed
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+
+            let wait = {
+                let now = tokio::time::Instant::now().into_std();
+                let mut state = self.state.lock().unwrap();
+                if state.waiting[priority.index() + 1..].iter().any(|n| *n > 0) {
+                    // let the higher priority waiters take the free capacity first
+                    None
+                } else {
+                    let mut wait = self.limit.per;
+                    for (i, id) in ids.iter().enumerate() {
+                        let bucket = state
+                            .buckets
+                            .entry(id.to_string())
+                            .or_insert_with(|| TokenBucket::new(self.limit, now));
+                        match bucket.try_take(now) {
+                            Ok(()) => return i,
+                            Err(w) => wait = wait.min(w),
+                        }
+                    }
+                    Some(wait)
+                }
+            };
+            match wait {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => released.await,
+            }
+        }
+    }
+}
+
+/// Counts a request as waiting on the limiter for as long as it is alive.
+struct Waiter<'a> {
+    limiter: &'a RateLimiter,
+    priority: Priority,
+}
+
+impl<'a> Waiter<'a> {
+    fn register(limiter: &'a RateLimiter, priority: Priority) -> Self {
+        limiter.state.lock().unwrap().waiting[priority.index()] += 1;
+        Self { limiter, priority }
+    }
+}
+
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        self.limiter.state.lock().unwrap().waiting[self.priority.index()] -= 1;
+        self.limiter.released.notify_waiters();
+    }
+}
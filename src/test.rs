@@ -1,27 +1,889 @@
-#[cfg(test)]
+#[cfg(all(test, feature = "client"))]
 mod tests {
-    use std::mem::transmute;
+    #[cfg(feature = "dev")]
     use crate::api;
-    use crate::api::{Client, ConfigForRezponse};
-    use std::time::Instant;
+    use crate::api::{ApiError, Client};
+    use crate::query::{ClanSearch, Query};
+    use std::time::{Duration, Instant};
+    #[cfg(feature = "extras")]
+    use crate::linking::{AccountLinker, LinkError, LinkStore, MemoryLinkStore};
+    use crate::models::current_war::{War, WarState};
+    use crate::models::war_log::WarResult;
+    use crate::models::player::{Player, PlayerToken, VerificationStatus};
+    use crate::models::ranking::PlayerRanking;
+    use crate::models::tag::{Tag, TagError};
+    #[cfg(feature = "lenient")]
+    use crate::models::clan::{Clan, Role};
+    use crate::rate_limit::{Priority, RateLimit, RateLimiter, TokenBucket};
+    use crate::retry::{self, RetryPolicy};
+    use crate::cache::{self, CacheBackend, CacheEntry, MemoryCache};
+    use crate::http::{HttpTransport, Request, Response, TransportError, TransportErrorKind};
+    use crate::middleware::Middleware;
+    use futures::future::BoxFuture;
+    use futures::StreamExt;
+    use http::header::{HeaderMap, HeaderValue, AUTHORIZATION, CACHE_CONTROL, RETRY_AFTER};
+    use http::{Method, StatusCode};
+    use std::collections::VecDeque;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
 
 
+    #[cfg(feature = "dev")]
     #[tokio::test]
     async fn benchmark() {
         println!("starting");
         let now = Instant::now();
 
         {
-            let client = api::Client::new(
+            let _client = api::Client::new(
                 std::env::var("CLASH_EMAIL").unwrap(),
                 std::env::var("CLASH_PASS").unwrap()
-            ).await;
+            ).await.unwrap();
         }
 
         let elapsed = now.elapsed();
         println!("Elapsed: {:.2?}", elapsed);
     }
 
+    #[cfg(feature = "dev")]
+    fn dev_key(id: &str, ips: &[&str]) -> crate::dev_models::existing_key::Key {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "developerId": "dev",
+            "tier": "developer/silver",
+            "name": id,
+            "description": "",
+            "scopes": ["clash"],
+            "cidrRanges": ips,
+            "key": format!("secret-{}", id),
+        }))
+        .unwrap()
+    }
+
+    #[cfg(feature = "dev")]
+    #[test]
+    fn keys_are_matched_to_egress_ips() {
+        let keys = vec![
+            dev_key("a", &["1.1.1.1"]),
+            dev_key("b", &["2.2.2.2"]),
+            dev_key("both", &["2.2.2.2", "1.1.1.1"]),
+            dev_key("elsewhere", &["9.9.9.9"]),
+        ];
+        let ips = vec![
+            Some("1.1.1.1".to_string()),
+            Some("2.2.2.2".to_string()),
+            Some("3.3.3.3".to_string()),
+            None,
+        ];
+
+        let matched: Vec<(String, usize)> = api::match_keys(&keys, &ips)
+            .into_iter()
+            .map(|key| (key.id, key.egress))
+            .collect();
+        // a key registered for two egresses is only used once, from the first
+        assert_eq!(
+            matched,
+            vec![
+                ("a".to_string(), 0),
+                ("both".to_string(), 0),
+                ("b".to_string(), 1),
+            ]
+        );
+
+        let matched = api::match_keys(&keys, &[Some("3.3.3.3".to_string()), None]);
+        assert!(matched.is_empty());
+    }
+
+    #[tokio::test]
+    async fn client_without_keys_errors_instead_of_panicking() {
+        let transport = FakeTransport::with_responses(vec![]);
+        let client = Client::with_transport(transport.clone(), vec![]);
+        let err = client.get_player("#2PP").await.unwrap_err();
+        assert!(err.is_no_keys());
+        assert_eq!(transport.sent(), 0);
+
+        let client = Client::with_transport(transport.clone(), vec![])
+            .with_rate_limit(RateLimit::new(10, Duration::from_secs(1)));
+        assert!(client.get_player("#2PP").await.unwrap_err().is_no_keys());
+    }
+
+    #[test]
+    fn verification_status() {
+        let ok: PlayerToken = serde_json::from_str(
+            r##"{"tag":"#2PP","token":"abc","status":"ok"}"##
+        ).unwrap();
+        assert_eq!(ok.status(), VerificationStatus::Ok);
+        assert!(ok.is_verified());
+
+        let invalid: PlayerToken = serde_json::from_str(
+            r##"{"tag":"#2PP","token":"abc","status":"invalid"}"##
+        ).unwrap();
+        assert!(!invalid.is_verified());
+    }
+
+    #[cfg(feature = "extras")]
+    #[tokio::test]
+    async fn link_store_refuses_relink() {
+        let linker = AccountLinker::new(MemoryLinkStore::new());
+        assert_eq!(linker.store().link("#2PP", "user-1").await, None);
+        assert_eq!(linker.store().link("#2PP", "user-2").await, Some("user-1".to_string()));
+        let tag: Tag = "2pp".parse().unwrap();
+        assert_eq!(linker.linked_user(&tag).await, Some("user-1".to_string()));
+        assert_eq!(linker.unlink(&tag).await, Some("user-1".to_string()));
+        assert_eq!(linker.linked_user(&tag).await, None);
+    }
+
+    #[cfg(feature = "extras")]
+    #[tokio::test]
+    async fn linker_verifies_tokens_before_linking() {
+        let transport = FakeTransport::with_responses(vec![
+            Response::new(StatusCode::OK, r##"{"tag":"#R8J","token":"bad","status":"invalid"}"##),
+            Response::new(StatusCode::OK, r##"{"tag":"#R8J","token":"good","status":"ok"}"##),
+        ]);
+        let client = Client::with_transport(transport.clone(), vec!["key".to_string()]);
+        let linker = Arc::new(AccountLinker::new(MemoryLinkStore::new()));
+        linker.store().link("#2PP", "user-1").await;
+
+        let err = linker
+            .link(&client, "#2PP", "abc".to_string(), "user-2".to_string())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LinkError::AlreadyLinked(ref user) if user == "user-1"));
+        assert_eq!(transport.sent(), 0);
+
+        let err = linker
+            .link(&client, "#R8J", "bad".to_string(), "user-2".to_string())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LinkError::InvalidToken));
+        assert_eq!(linker.store().linked_user("#R8J").await, None);
+
+        // bots link from spawned command handlers
+        let spawned = {
+            let (linker, client) = (linker.clone(), client.clone());
+            tokio::spawn(async move {
+                linker
+                    .link(&client, "r8j", "good".to_string(), "user-2".to_string())
+                    .await
+            })
+        };
+        spawned.await.unwrap().unwrap();
+        assert_eq!(linker.store().linked_user("#R8J").await, Some("user-2".to_string()));
+        assert_eq!(transport.sent(), 2);
+    }
+
+    #[test]
+    fn tags_are_normalized_and_validated() {
+        let tag: Tag = " #2pp8o ".parse().unwrap();
+        assert_eq!(tag.as_str(), "#2PP80");
+        assert_eq!(tag.url_encoded(), "%232PP80");
+        assert_eq!(Tag::try_from("2PP80").unwrap(), tag);
+        assert_eq!("#".parse::<Tag>(), Err(TagError::Empty));
+        assert_eq!("#2PA".parse::<Tag>(), Err(TagError::InvalidChar('A')));
+
+        assert_eq!("#0PP".parse::<Tag>(), Err(TagError::LeadingZero));
+        assert_eq!("#2222222222222222".parse::<Tag>().map(|tag| tag.to_id() > 0), Ok(true));
+        assert_eq!("#22222222222222222".parse::<Tag>(), Err(TagError::TooLong));
+
+        assert_eq!(serde_json::to_string(&tag).unwrap(), r##""#2PP80""##);
+        assert!(serde_json::from_str::<Tag>(r##""#XYZ""##).is_err());
+    }
+
+    #[test]
+    fn token_bucket_refills() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(RateLimit::new(2, Duration::from_secs(1)), start);
+        assert!(bucket.try_take(start).is_ok());
+        assert!(bucket.try_take(start).is_ok());
+
+        let wait = bucket.try_take(start).unwrap_err();
+        assert_eq!(wait, Duration::from_millis(500));
+        assert!(bucket.try_take(start + wait).is_ok());
+    }
+
+    #[tokio::test]
+    async fn rate_limiter_spreads_over_keys() {
+        let limiter = RateLimiter::new(RateLimit::per_second(1));
+        let keys = ["a", "b"];
+        assert_eq!(limiter.acquire(&keys, Priority::Normal).await, 0);
+        assert_eq!(limiter.acquire(&keys, Priority::Normal).await, 1);
+    }
+
+    #[tokio::test]
+    async fn rate_limiter_serves_higher_priority_first() {
+        tokio::time::pause();
+        let limiter = Arc::new(RateLimiter::new(RateLimit::new(1, Duration::from_millis(50))));
+        let keys = ["a"];
+        limiter.acquire(&keys, Priority::Normal).await;
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let background = {
+            let (limiter, order) = (limiter.clone(), order.clone());
+            tokio::spawn(async move {
+                limiter.acquire(&["a"], Priority::Background).await;
+                order.lock().unwrap().push(Priority::Background);
+            })
+        };
+        // with the clock paused this only returns once the background request is waiting
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let interactive = {
+            let (limiter, order) = (limiter.clone(), order.clone());
+            tokio::spawn(async move {
+                limiter.acquire(&["a"], Priority::Interactive).await;
+                order.lock().unwrap().push(Priority::Interactive);
+            })
+        };
+        background.await.unwrap();
+        interactive.await.unwrap();
+        assert_eq!(*order.lock().unwrap(), vec![Priority::Interactive, Priority::Background]);
+    }
+
+    #[test]
+    fn retry_backoff_and_retry_after() {
+        let policy = RetryPolicy::new(5)
+            .with_base_delay(Duration::from_millis(100))
+            .with_max_delay(Duration::from_millis(300))
+            .with_jitter(false);
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(300));
+        assert!(!policy.retries_non_idempotent());
+        assert!(policy.retries_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!policy.retries_status(StatusCode::NOT_FOUND));
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry::retry_after(&headers), None);
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        assert_eq!(retry::retry_after(&headers), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn cache_honors_max_age() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("public, max-age=120"));
+        assert_eq!(cache::max_age(&headers), Some(Duration::from_secs(120)));
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+        assert_eq!(cache::max_age(&headers), None);
+
+        assert!(!CacheEntry::new("{}".to_string(), Duration::from_secs(60)).is_expired());
+        assert!(CacheEntry::new("{}".to_string(), Duration::ZERO).is_expired());
+    }
+
+    #[test]
+    fn memory_cache_evicts_least_recently_used() {
+        let cache = MemoryCache::new(2);
+        let entry = || CacheEntry::new("{}".to_string(), Duration::from_secs(60));
+        cache.insert("a", entry());
+        cache.insert("b", entry());
+        assert!(cache.get("a").is_some());
+        cache.insert("c", entry());
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.len(), 2);
+
+        let cache = MemoryCache::new(10).with_max_bytes(8);
+        cache.insert("a", CacheEntry::new("1234".to_string(), Duration::from_secs(60)));
+        cache.insert("b", CacheEntry::new("1234".to_string(), Duration::from_secs(60)));
+        assert!(cache.get("a").is_none());
+        assert!(cache.get("b").is_some());
+    }
+
+    #[cfg(feature = "extras")]
+    #[test]
+    fn disk_cache_persists_entries() {
+        let dir = std::env::temp_dir().join(format!("coc-rs-cache-{}", std::process::id()));
+        let url = "https://api.clashofclans.com/v1/clans/%232PP";
+        {
+            let cache = crate::cache::DiskCache::new(&dir).unwrap();
+            cache.insert(url, CacheEntry::new("{\"tag\":\"#2PP\"}".to_string(), Duration::from_secs(60)));
+        }
+        let cache = crate::cache::DiskCache::new(&dir).unwrap();
+        assert_eq!(cache.get(url).unwrap().body(), "{\"tag\":\"#2PP\"}");
+        assert!(cache.get("https://api.clashofclans.com/v1/clans/%23R8J").is_none());
+        cache.clear();
+        assert!(cache.get(url).is_none());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "extras")]
+    #[test]
+    fn disk_cache_only_deletes_its_own_files() {
+        let dir = std::env::temp_dir().join(format!("coc-rs-cache-shared-{}", std::process::id()));
+        let cache = crate::cache::DiskCache::new(&dir).unwrap();
+        cache.insert("https://a", CacheEntry::new("{}".to_string(), Duration::from_secs(60)));
+        cache.insert("https://b", CacheEntry::new("{}".to_string(), Duration::ZERO));
+        std::fs::write(dir.join("settings.json"), "not a cache entry").unwrap();
+        std::fs::write(dir.join("coc-notes.json"), "").unwrap();
+        let stale = dir.join("coc-0123456789abcdef.1-0.tmp");
+        let fresh = dir.join("coc-0123456789abcdef.1-1.tmp");
+        std::fs::write(&stale, "").unwrap();
+        std::fs::write(&fresh, "").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&stale)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - Duration::from_secs(120))
+            .unwrap();
+
+        cache.purge_expired().unwrap();
+        assert!(cache.get("https://a").is_some());
+        assert!(cache.get("https://b").is_none());
+        assert!(!stale.exists() && fresh.exists());
+        assert!(dir.join("settings.json").exists() && dir.join("coc-notes.json").exists());
+
+        cache.clear();
+        assert!(cache.get("https://a").is_none());
+        assert!(dir.join("settings.json").exists() && dir.join("coc-notes.json").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "extras")]
+    #[test]
+    fn disk_cache_concurrent_writes_do_not_collide() {
+        let dir = std::env::temp_dir().join(format!("coc-rs-cache-concurrent-{}", std::process::id()));
+        let url = "https://api.clashofclans.com/v1/clans/%232PP";
+        let cache = Arc::new(crate::cache::DiskCache::new(&dir).unwrap());
+        let writers: Vec<_> = (0..8)
+            .map(|i| {
+                let cache = cache.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..20 {
+                        cache.insert(url, CacheEntry::new(format!("{{\"n\":{}}}", i), Duration::from_secs(60)));
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        assert!(cache.get(url).is_some());
+        let files = std::fs::read_dir(&dir).unwrap().count();
+        assert_eq!(files, 1, "temp files were left behind");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[derive(Debug, Default)]
+    struct FakeTransport {
+        responses: Mutex<VecDeque<Response>>,
+        requests: Mutex<Vec<Request>>,
+    }
+
+    impl FakeTransport {
+        fn with_responses(responses: Vec<Response>) -> Arc<Self> {
+            Arc::new(Self {
+                responses: Mutex::new(responses.into()),
+                requests: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn sent(&self) -> usize {
+            self.requests.lock().unwrap().len()
+        }
+    }
+
+    impl HttpTransport for FakeTransport {
+        fn send(&self, request: Request) -> BoxFuture<'_, Result<Response, TransportError>> {
+            Box::pin(async move {
+                self.requests.lock().unwrap().push(request);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                self.responses
+                    .lock()
+                    .unwrap()
+                    .pop_front()
+                    .ok_or_else(|| TransportError::new(TransportErrorKind::Connect, "no response"))
+            })
+        }
+    }
+
+    /// Answers every player request by tag, 404 for `missing`, and records how many
+    /// requests were in flight at once.
+    #[derive(Debug, Default)]
+    struct BulkTransport {
+        missing: String,
+        in_flight: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    impl HttpTransport for BulkTransport {
+        fn send(&self, request: Request) -> BoxFuture<'_, Result<Response, TransportError>> {
+            Box::pin(async move {
+                let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                self.in_flight.fetch_sub(1, Ordering::SeqCst);
+                if request.url().ends_with(&self.missing) {
+                    return Ok(Response::new(StatusCode::NOT_FOUND, r#"{"reason":"notFound"}"#));
+                }
+                Ok(Response::new(StatusCode::OK, serde_json::to_string(&Player::default()).unwrap()))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn bulk_requests_survive_failures_and_respect_concurrency() {
+        let transport = Arc::new(BulkTransport {
+            missing: "%23R8J".to_string(),
+            ..Default::default()
+        });
+        let client = Client::with_transport(transport.clone(), vec!["key".to_string()])
+            .with_bulk_concurrency(2);
+        let tags = ["#2PP", "r8j", "#2QQ", "2gg", "#2RR", "#NOPE"];
+
+        let results: HashMap<String, Result<Player, ApiError>> = client.get_players(tags).collect().await;
+        assert_eq!(results.len(), tags.len());
+        assert!(results["#R8J"].as_ref().unwrap_err().is_not_found());
+        assert!(results["#2GG"].is_ok());
+        assert!(results["#NOPE"].as_ref().unwrap_err().is_invalid_tag());
+        assert_eq!(results.values().filter(|res| res.is_ok()).count(), 4);
+        assert_eq!(transport.peak.load(Ordering::SeqCst), 2);
+
+        let results: Vec<_> = client.get_clans(tags).collect().await;
+        assert_eq!(results.len(), tags.len());
+        assert_eq!(transport.peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[derive(Debug)]
+    struct CannedWar;
+
+    impl Middleware for CannedWar {
+        fn before_request(&self, _request: &mut Request) -> Option<Response> {
+            Some(Response::new(StatusCode::OK, r#"{"state":"warEnded"}"#))
+        }
+    }
+
+    const NOT_IN_WAR: &str = r#"{"state":"notInWar"}"#;
+
+    #[tokio::test]
+    async fn transport_receives_authorized_requests() {
+        let transport = FakeTransport::with_responses(vec![Response::new(
+            StatusCode::OK,
+            r##"{"tag":"#2PP","token":"abc","status":"ok"}"##,
+        )]);
+        let client = Client::with_transport(transport.clone(), vec!["key".to_string()]);
+
+        let token = client.get_verified_player("#2PP".to_string(), "abc".to_string()).await.unwrap();
+        assert!(token.is_verified());
+
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests[0].method(), Method::POST);
+        assert_eq!(requests[0].url(), "https://api.clashofclans.com/v1/players/%232PP/verifytoken");
+        assert_eq!(requests[0].headers()[AUTHORIZATION], "Bearer key");
+        assert_eq!(requests[0].body().as_deref(), Some(r#"{"token":"abc"}"#));
+    }
+
+    #[tokio::test]
+    async fn concurrent_gets_are_coalesced() {
+        let transport = FakeTransport::with_responses(vec![Response::new(StatusCode::OK, NOT_IN_WAR)]);
+        let client = Client::with_transport(transport.clone(), vec!["key".to_string()]);
+
+        let (a, b) = futures::join!(
+            client.get_current_war("#R8J".to_string()),
+            client.get_current_war("#R8J".to_string())
+        );
+        assert_eq!(a.unwrap().state(), &WarState::NotInWar);
+        assert_eq!(b.unwrap().state(), &WarState::NotInWar);
+        assert_eq!(transport.sent(), 1);
+    }
+
+    #[tokio::test]
+    async fn cached_responses_skip_the_transport() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("max-age=60"));
+        let transport = FakeTransport::with_responses(vec![
+            Response::new(StatusCode::OK, NOT_IN_WAR).with_headers(headers),
+            Response::new(StatusCode::OK, NOT_IN_WAR),
+        ]);
+        let client = Client::with_transport(transport.clone(), vec!["key".to_string()]).with_cache();
+
+        client.get_current_war("#R8J".to_string()).await.unwrap();
+        client.get_current_war("#R8J".to_string()).await.unwrap();
+        assert_eq!(transport.sent(), 1);
+        client.without_cache().get_current_war("#R8J".to_string()).await.unwrap();
+        assert_eq!(transport.sent(), 2);
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried() {
+        let transport = FakeTransport::with_responses(vec![
+            Response::new(StatusCode::SERVICE_UNAVAILABLE, "{}"),
+            Response::new(StatusCode::OK, NOT_IN_WAR),
+        ]);
+        let client = Client::with_transport(transport.clone(), vec!["key".to_string()])
+            .with_retry_policy(RetryPolicy::new(3).with_base_delay(Duration::from_millis(1)));
+
+        client.get_current_war("#R8J".to_string()).await.unwrap();
+        assert_eq!(transport.sent(), 2);
+    }
+
+    #[tokio::test]
+    async fn long_retry_after_is_not_waited_for() {
+        let mut throttled = Response::new(StatusCode::TOO_MANY_REQUESTS, "{}");
+        throttled.headers_mut().insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        let transport = FakeTransport::with_responses(vec![throttled, Response::new(StatusCode::OK, NOT_IN_WAR)]);
+        let client = Client::with_transport(transport.clone(), vec!["key".to_string()])
+            .with_retry_policy(RetryPolicy::new(3).with_max_delay(Duration::from_secs(1)));
+
+        let err = client.get_current_war("#R8J".to_string()).await.unwrap_err();
+        assert!(err.is_throttled());
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(120)));
+        assert_eq!(transport.sent(), 1);
+    }
+
+    #[tokio::test]
+    async fn middleware_can_short_circuit() {
+        let transport = FakeTransport::with_responses(vec![]);
+        let client = Client::with_transport(transport.clone(), vec!["key".to_string()])
+            .with_middleware(CannedWar);
+
+        let war = client.get_current_war("#R8J".to_string()).await.unwrap();
+        assert_eq!(war.state(), &WarState::WarEnded);
+        assert_eq!(transport.sent(), 0);
+    }
+
+    /// Counts the outcomes middleware gets to see.
+    #[derive(Debug, Default)]
+    struct Outcomes {
+        responses: AtomicUsize,
+        errors: Mutex<Vec<String>>,
+    }
+
+    impl Middleware for Arc<Outcomes> {
+        fn after_response(&self, _request: &Request, _response: &mut Response) {
+            self.responses.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn on_error(&self, request: &Request, error: &ApiError) {
+            self.errors.lock().unwrap().push(format!("{} {}", request.url(), error.is_network()));
+        }
+    }
+
+    #[tokio::test]
+    async fn middleware_sees_failed_attempts() {
+        let outcomes = Arc::new(Outcomes::default());
+        // the second attempt finds no response and fails to connect
+        let transport = FakeTransport::with_responses(vec![Response::new(StatusCode::SERVICE_UNAVAILABLE, "{}")]);
+        let client = Client::with_transport(transport, vec!["key".to_string()])
+            .with_retry_policy(RetryPolicy::new(2).with_base_delay(Duration::from_millis(1)))
+            .with_middleware(outcomes.clone());
+
+        let err = client.get_current_war("#R8J").await.unwrap_err();
+        assert!(err.is_network());
+        assert_eq!(outcomes.responses.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            *outcomes.errors.lock().unwrap(),
+            ["https://api.clashofclans.com/v1/clans/%23R8J/currentwar true"]
+        );
+
+        let client = Client::with_transport(FakeTransport::with_responses(vec![]), vec![])
+            .with_middleware(outcomes.clone());
+        assert!(client.get_current_war("#R8J").await.unwrap_err().is_no_keys());
+        assert_eq!(outcomes.errors.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn maintenance_opens_the_circuit() {
+        let transport = FakeTransport::with_responses(vec![
+            Response::new(StatusCode::SERVICE_UNAVAILABLE, r#"{"reason":"inMaintenance"}"#),
+            Response::new(StatusCode::OK, "{}"),
+            Response::new(StatusCode::OK, NOT_IN_WAR),
+        ]);
+        let client = Client::with_transport(transport.clone(), vec!["key".to_string()])
+            .with_retry_policy(RetryPolicy::new(3).with_base_delay(Duration::from_millis(1)))
+            .with_maintenance_probe_interval(Duration::from_millis(50));
+        let mut state = client.subscribe_maintenance();
+
+        let err = client.get_current_war("#R8J".to_string()).await.unwrap_err();
+        assert!(matches!(err, ApiError::Maintenance));
+        assert!(client.in_maintenance());
+        assert!(*state.borrow_and_update());
+
+        let err = client.get_current_war("#R8J".to_string()).await.unwrap_err();
+        assert!(matches!(err, ApiError::Maintenance));
+        assert_eq!(transport.sent(), 1);
+
+        // the background probe closes the circuit
+        state.changed().await.unwrap();
+        assert!(!*state.borrow());
+        assert!(!client.in_maintenance());
+        client.get_current_war("#R8J".to_string()).await.unwrap();
+        assert_eq!(transport.sent(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn maintenance_is_probed_without_callers() {
+        let transport = FakeTransport::with_responses(vec![
+            Response::new(StatusCode::SERVICE_UNAVAILABLE, r#"{"reason":"inMaintenance"}"#),
+            Response::new(StatusCode::SERVICE_UNAVAILABLE, r#"{"reason":"inMaintenance"}"#),
+            Response::new(StatusCode::OK, "{}"),
+        ]);
+        let client = Client::with_transport(transport.clone(), vec!["key".to_string()])
+            .with_maintenance_probe_interval(Duration::from_secs(30));
+        let mut state = client.subscribe_maintenance();
+
+        client.get_current_war("#R8J".to_string()).await.unwrap_err();
+        assert!(*state.borrow_and_update());
+
+        let start = tokio::time::Instant::now();
+        tokio::time::timeout(Duration::from_secs(120), state.changed())
+            .await
+            .expect("the probe never closed the circuit")
+            .unwrap();
+        assert!(!*state.borrow());
+        assert!(start.elapsed() >= Duration::from_secs(60));
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[2].url().ends_with("/goldpass/seasons/current"));
+    }
+
+    #[tokio::test]
+    async fn api_errors_keep_the_error_body() {
+        let transport = FakeTransport::with_responses(vec![Response::new(
+            StatusCode::FORBIDDEN,
+            r#"{"reason":"accessDenied.invalidIp","message":"Invalid authorization: API key does not allow access from IP 1.2.3.4"}"#,
+        )]);
+        let client = Client::with_transport(transport, vec!["key".to_string()]);
+
+        let err = client.get_current_war("#R8J".to_string()).await.unwrap_err();
+        assert!(err.is_invalid_ip() && err.is_access_denied());
+        assert_eq!(err.reason(), Some("accessDenied.invalidIp"));
+        assert!(err.to_string().contains("(accessDenied.invalidIp: Invalid authorization"));
+    }
+
+    #[test]
+    fn tags_round_trip_through_ids() {
+        let tag: Tag = "#2PP".parse().unwrap();
+        assert_eq!(tag.to_id(), 196 + 4 * 14 + 4);
+        assert_eq!(tag.to_high_low(), Some((0, 1)));
+        assert_eq!(Tag::from_high_low(0, 1), Some(tag));
+
+        for id in [1, 13, 14, 255, 256, 123_456_789, 1 << 39, u32::MAX as u64 * 256 + 255] {
+            let tag = Tag::from_id(id).unwrap();
+            assert_eq!(tag.as_str().parse::<Tag>().unwrap().to_id(), id);
+            let (high, low) = tag.to_high_low().unwrap();
+            assert_eq!(Tag::from_high_low(high, low), Some(tag));
+        }
+        for _ in 0..1000 {
+            let tag = Tag::from_id(fastrand::u64(1..14u64.pow(16))).unwrap();
+            assert_eq!(Tag::from_id(tag.to_id()), Some(tag));
+        }
+        assert_eq!(Tag::from_id(0), None);
+        assert_eq!(Tag::from_id(u64::MAX), None);
+        assert_eq!(Tag::from_id(1 << 40).unwrap().to_high_low(), None);
+        assert_eq!(Tag::from_high_low(256, 1), None);
+    }
+
+    #[tokio::test]
+    async fn malformed_tags_are_rejected_before_sending() {
+        let transport = FakeTransport::with_responses(vec![]);
+        let client = Client::with_transport(transport.clone(), vec!["key".to_string()]);
+
+        let err = client.get_player("#NOT-A-TAG").await.unwrap_err();
+        assert!(err.is_invalid_tag());
+        let members: Vec<_> = client.get_clan_members_stream("", None).collect().await;
+        assert!(members[0].as_ref().unwrap_err().is_invalid_tag());
+        assert_eq!(transport.sent(), 0);
+    }
+
+    #[tokio::test]
+    async fn api_errors_are_classified() {
+        let mut throttled = Response::new(StatusCode::TOO_MANY_REQUESTS, "");
+        throttled.headers_mut().insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        let transport = FakeTransport::with_responses(vec![
+            Response::new(StatusCode::NOT_FOUND, r#"{"reason":"notFound"}"#),
+            Response::new(StatusCode::FORBIDDEN, r#"{"reason":"accessDenied"}"#),
+            Response::new(
+                StatusCode::FORBIDDEN,
+                r#"{"reason":"accessDenied","message":"Access denied, clan war log is private."}"#,
+            ),
+            throttled,
+            Response::new(StatusCode::OK, r#"{"endTime":1}"#),
+        ]);
+        let client = Client::with_transport(transport, vec!["key".to_string()]);
+
+        let err = client.get_player("#R8J".to_string()).await.unwrap_err();
+        assert!(err.is_not_found() && !err.is_retryable());
+        let err = client.get_clan_warlog("#R8J".to_string(), Query::new()).await.unwrap_err();
+        assert!(err.is_access_denied() && !err.is_private_war_log());
+        let err = client.get_clan_warlog("#R8J".to_string(), Query::new()).await.unwrap_err();
+        assert!(err.is_private_war_log());
+        let err = client.get_clan("#R8J".to_string()).await.unwrap_err();
+        assert!(err.is_throttled() && err.is_retryable());
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(7)));
+        let err = client.get_goldpass().await.unwrap_err();
+        assert!(err.is_deserialize());
+    }
+
+    #[tokio::test]
+    async fn model_drift_is_reported_with_its_path() {
+        let transport = FakeTransport::with_responses(vec![Response::new(
+            StatusCode::OK,
+            r#"{"state":"inWar","clan":{"members":[{"name":7}]}}"#,
+        )]);
+        let client = Client::with_transport(transport, vec!["key".to_string()]);
+
+        let err = match client.get_current_war("#R8J".to_string()).await.unwrap_err() {
+            ApiError::Deserialize(err) => err,
+            err => panic!("unexpected error {:?}", err),
+        };
+        assert!(err.snippet().starts_with(r#"{"state":"inWar""#));
+        assert_eq!(err.path(), "clan.members[0].name");
+    }
+
+    fn member_page(tags: &[&str], after: Option<&str>) -> Response {
+        let members: Vec<String> = tags
+            .iter()
+            .map(|tag| {
+                format!(
+                    r#"{{"tag":"{}","name":"m","role":"member","expLevel":1,"trophies":0,"versusTrophies":0,
+                        "clanRank":1,"previousClanRank":1,"donations":0,"donationsReceived":0}}"#,
+                    tag
+                )
+            })
+            .collect();
+        let cursors = after.map(|after| format!(r#"{{"after":"{}"}}"#, after)).unwrap_or("{}".to_string());
+        Response::new(
+            StatusCode::OK,
+            format!(r#"{{"items":[{}],"paging":{{"cursors":{}}}}}"#, members.join(","), cursors),
+        )
+    }
+
+    #[tokio::test]
+    async fn streams_follow_the_after_cursor() {
+        let transport = FakeTransport::with_responses(vec![
+            member_page(&["#A", "#B"], Some("c1")),
+            member_page(&["#C"], None),
+            member_page(&["#A", "#B"], Some("c1")),
+            member_page(&["#C", "#D"], Some("c2")),
+        ]);
+        let client = Client::with_transport(transport.clone(), vec!["key".to_string()]);
+
+        let tags: Vec<String> = client
+            .get_clan_members_stream("#R8J".to_string(), None)
+            .map(|member| member.unwrap().tag().to_string())
+            .collect()
+            .await;
+        assert_eq!(tags, ["#A", "#B", "#C"]);
+        assert!(transport.requests.lock().unwrap()[1].url().ends_with("/members?after=c1"));
+
+        let members: Vec<_> = client.get_clan_members_stream("#R8J".to_string(), Some(3)).collect().await;
+        assert_eq!(members.len(), 3);
+        assert_eq!(transport.sent(), 4);
+        assert!(transport.requests.lock().unwrap()[3].url().ends_with("/members?limit=1&after=c1"));
+    }
+
+    fn ranking_page(count: usize, after: Option<&str>) -> Response {
+        let ranking = serde_json::to_string(&PlayerRanking::default()).unwrap();
+        let items = vec![ranking; count].join(",");
+        let cursors = after.map(|after| format!(r#"{{"after":"{}"}}"#, after)).unwrap_or("{}".to_string());
+        Response::new(
+            StatusCode::OK,
+            format!(r#"{{"items":[{}],"paging":{{"cursors":{}}}}}"#, items, cursors),
+        )
+    }
+
+    #[tokio::test]
+    async fn ranking_streams_follow_the_after_cursor() {
+        let transport = FakeTransport::with_responses(vec![
+            ranking_page(2, Some("c1")),
+            ranking_page(2, None),
+        ]);
+        let client = Client::with_transport(transport.clone(), vec!["key".to_string()]);
+
+        let rankings: Vec<_> = client.get_player_rankings_stream(32000006, None).collect().await;
+        assert_eq!(rankings.len(), 4);
+        assert!(rankings.iter().all(Result::is_ok));
+        let requests = transport.requests.lock().unwrap();
+        assert!(requests[0].url().ends_with("/locations/32000006/rankings/players"));
+        assert!(requests[1].url().ends_with("/rankings/players?after=c1"));
+    }
+
+    #[tokio::test]
+    async fn pages_replay_their_query() {
+        let transport = FakeTransport::with_responses(vec![
+            member_page(&["#A", "#B"], Some("c1")),
+            member_page(&["#C"], None),
+        ]);
+        let client = Client::with_transport(transport.clone(), vec!["key".to_string()]);
+
+        let page = client.get_clan_members("#R8J".to_string(), Query::new().limit(2)).await.unwrap();
+        assert_eq!(page.items().len(), 2);
+        assert_eq!(page.next_cursor(), Some("c1"));
+        assert!(client.prev_page(&page).await.unwrap().is_none());
+
+        let last = client.next_page(&page).await.unwrap().unwrap();
+        assert!(transport.requests.lock().unwrap()[1].url().ends_with("/members?limit=2&after=c1"));
+        assert_eq!(last.into_items()[0].tag(), "#C");
+        assert_eq!(transport.sent(), 2);
+    }
+
+    #[test]
+    fn queries_are_url_encoded() {
+        let query = Query::new().limit(5).after("eyJwb3MiOjV9==").before("b");
+        assert_eq!(query.to_string(), "limit=5&before=b");
+        let query = query.after("eyJwb3MiOjV9==");
+        assert_eq!(query.to_string(), "limit=5&after=eyJwb3MiOjV9%3D%3D");
+
+        let search: Query = ClanSearch::new().name("war & peace").min_members(10).label_ids(&[1, 2]).limit(3).into();
+        assert_eq!(search.to_string(), "name=war+%26+peace&minMembers=10&labelIds=1%2C2&limit=3");
+        assert_eq!(search.append_to("https://x/clans?a=1"), format!("https://x/clans?a=1&{}", search));
+    }
+
+    #[test]
+    fn war_states_and_results_are_typed() {
+        let war: War = serde_json::from_str(r#"{"state":"preparation"}"#).unwrap();
+        assert!(war.is_active() && !war.has_ended());
+        let war: War = serde_json::from_str(r#"{"state":"cwlBreak"}"#).unwrap();
+        assert_eq!(war.state(), &WarState::Unknown("cwlBreak".to_string()));
+        assert_eq!(serde_json::to_string(war.state()).unwrap(), r#""cwlBreak""#);
+
+        assert_eq!(serde_json::from_str::<Option<WarResult>>(r#""tie""#).unwrap(), Some(WarResult::Tie));
+        assert_eq!(serde_json::from_str::<Option<WarResult>>("null").unwrap(), None);
+        assert_eq!(WarResult::Lose.to_string(), "lose");
+    }
+
+    #[test]
+    fn unknown_fields_are_kept() {
+        let war: War = serde_json::from_str(
+            r##"{"state":"inWar","battleModifier":"none","clan":{"badgeUrls":{"small":"","large":"","medium":""},
+                "members":[{"tag":"#R8J","name":"a","townhallLevel":9,"mapPosition":1,"opponentAttacks":0}]}}"##,
+        )
+        .unwrap();
+        assert_eq!(war.extra()["battleModifier"], "none");
+        let members = war.clan().as_ref().unwrap().members().as_ref().unwrap();
+        assert!(members[0].best_opponent_attack().is_none());
+    }
+
+    #[cfg(feature = "lenient")]
+    #[test]
+    fn lenient_models_default_missing_fields() {
+        let clan: Clan = serde_json::from_str(
+            r##"{"tag":"#R8J","name":"clan","memberList":[{"tag":"#2PP","role":"grandmaster"}]}"##,
+        )
+        .unwrap();
+        assert!(clan.chat_language().is_none() && clan.labels().is_empty());
+        assert!(clan.member_list()[0].league().is_none());
+        let role = clan.member_list()[0].role();
+        assert_eq!(role, &Role::Unknown("grandmaster".to_string()));
+        assert_eq!(role.as_str(), "grandmaster");
+        assert!(serde_json::to_string(&clan).unwrap().contains(r#""role":"grandmaster""#));
+        assert_eq!(serde_json::from_str::<Role>(r#""coLeader""#).unwrap(), Role::CoLeader);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn blocking_client_mirrors_endpoints() {
+        let transport = FakeTransport::with_responses(vec![
+            Response::new(StatusCode::OK, NOT_IN_WAR),
+            member_page(&["#A", "#B"], Some("c1")),
+            member_page(&["#C"], None),
+        ]);
+        let client = crate::blocking::BlockingClient::with_transport(transport, vec!["key".to_string()]);
+        assert_eq!(client.get_current_war("#R8J".to_string()).unwrap().state(), &WarState::NotInWar);
+        assert_eq!(client.get_all_clan_members("#R8J", None).unwrap().len(), 3);
+    }
+
     // #[tokio::test]
     // async fn test_player() {
     //     let client = api::Client::new(